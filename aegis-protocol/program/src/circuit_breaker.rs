@@ -0,0 +1,219 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{
+        BPS_DENOMINATOR, CIRCUIT_BREAKER_MAX_VIOLATIONS, CIRCUIT_BREAKER_STRIKE_THRESHOLD_BPS,
+        CIRCUIT_BREAKER_WINDOW_SECONDS,
+    },
+    errors::ErrorCode,
+    math::U256,
+    state::Pool,
+};
+
+#[event]
+pub struct CircuitBreakerStrike {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub price_impact_bps: u64,
+    pub window_move_bps: u64,
+    pub violations: u8,
+    pub emergency_mode: bool,
+}
+
+#[event]
+pub struct CircuitBreakerConfigured {
+    pub pool: Pubkey,
+    pub max_price_impact_bps: u16,
+    pub max_window_move_bps: u16,
+}
+
+#[event]
+pub struct PoolResumed {
+    pub pool: Pubkey,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureCircuitBreaker<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump,
+        has_one = creator @ ErrorCode::Unauthorized
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Limits are in basis points of the spot price of A in B; zero disables a limit.
+pub fn configure_circuit_breaker(
+    ctx: Context<ConfigureCircuitBreaker>,
+    max_price_impact_bps: u16,
+    max_window_move_bps: u16,
+) -> Result<()> {
+    require!(
+        (max_price_impact_bps as u64) <= BPS_DENOMINATOR && (max_window_move_bps as u64) <= BPS_DENOMINATOR,
+        ErrorCode::InvalidAmount
+    );
+    let pool = &mut ctx.accounts.pool;
+    pool.max_price_impact_bps = max_price_impact_bps;
+    pool.max_window_move_bps = max_window_move_bps;
+    emit!(CircuitBreakerConfigured {
+        pool: pool.key(),
+        max_price_impact_bps,
+        max_window_move_bps,
+    });
+    Ok(())
+}
+
+/// Lifts emergency mode and clears the breaker's strikes and window.
+pub fn resume_pool(ctx: Context<ConfigureCircuitBreaker>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.emergency_mode = false;
+    pool.breaker_violations = 0;
+    pool.window_start_ts = 0;
+    pool.window_start_price = 0;
+    emit!(PoolResumed { pool: pool.key() });
+    Ok(())
+}
+
+/// How far a swap would move the price, from the pre-trade price and from the window's reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PriceMove {
+    pub price_impact_bps: u64,
+    pub window_move_bps: u64,
+}
+
+/// Measures a swap that leaves the pool at `post_reserve_a`/`post_reserve_b`, opening a new window
+/// at the pre-trade price if the last one expired. A move past a limit fails the swap. A move past
+/// `CIRCUIT_BREAKER_STRIKE_THRESHOLD_BPS` of a limit still executes but is returned as a strike,
+/// and `CIRCUIT_BREAKER_MAX_VIOLATIONS` strikes within one window put the pool into emergency mode.
+pub(crate) fn check_swap(
+    pool: &mut Pool,
+    post_reserve_a: u64,
+    post_reserve_b: u64,
+    now: i64,
+) -> Result<Option<PriceMove>> {
+    if !is_enabled(pool) {
+        return Ok(None);
+    }
+    let (Some(price_before), Some(price_after)) = (
+        spot_price(pool.reserve_a, pool.reserve_b),
+        spot_price(post_reserve_a, post_reserve_b),
+    ) else {
+        return Ok(None);
+    };
+
+    if pool.window_start_price == 0 || now >= pool.window_start_ts.saturating_add(CIRCUIT_BREAKER_WINDOW_SECONDS) {
+        pool.window_start_ts = now;
+        pool.window_start_price = price_before;
+        pool.breaker_violations = 0;
+    }
+
+    let price_move = PriceMove {
+        price_impact_bps: deviation_bps(price_before, price_after),
+        window_move_bps: deviation_bps(pool.window_start_price, price_after),
+    };
+    let exceeds_share = |share_bps: u64| {
+        exceeds(price_move.price_impact_bps, pool.max_price_impact_bps, share_bps)
+            || exceeds(price_move.window_move_bps, pool.max_window_move_bps, share_bps)
+    };
+    require!(!exceeds_share(BPS_DENOMINATOR), ErrorCode::CircuitBreakerTripped);
+    if !exceeds_share(CIRCUIT_BREAKER_STRIKE_THRESHOLD_BPS) {
+        return Ok(None);
+    }
+
+    pool.breaker_violations = pool.breaker_violations.saturating_add(1);
+    if pool.breaker_violations >= CIRCUIT_BREAKER_MAX_VIOLATIONS {
+        pool.emergency_mode = true;
+    }
+    Ok(Some(price_move))
+}
+
+pub(crate) fn is_enabled(pool: &Pool) -> bool {
+    pool.max_price_impact_bps != 0 || pool.max_window_move_bps != 0
+}
+
+/// Whether `move_bps` is past `share_bps` of `limit_bps`; a zero limit is never exceeded.
+fn exceeds(move_bps: u64, limit_bps: u16, share_bps: u64) -> bool {
+    limit_bps != 0 && move_bps as u128 * BPS_DENOMINATOR as u128 > limit_bps as u128 * share_bps as u128
+}
+
+/// Q64.64 price of A in B, or `None` for an empty side.
+fn spot_price(reserve_a: u64, reserve_b: u64) -> Option<u128> {
+    if reserve_a == 0 || reserve_b == 0 {
+        return None;
+    }
+    Some(((reserve_b as u128) << 64) / reserve_a as u128)
+}
+
+fn deviation_bps(reference: u128, price: u128) -> u64 {
+    let bps = U256::from(reference.abs_diff(price)) * U256::from(BPS_DENOMINATOR) / U256::from(reference);
+    bps.min(U256::from(u64::MAX)).as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker_pool(max_price_impact_bps: u16, max_window_move_bps: u16) -> Pool {
+        let mut pool: Pool = Pool::try_deserialize_unchecked(&mut [0u8; Pool::SIZE].as_slice()).unwrap();
+        pool.set_reserves(1_000_000, 1_000_000);
+        pool.max_price_impact_bps = max_price_impact_bps;
+        pool.max_window_move_bps = max_window_move_bps;
+        pool
+    }
+
+    #[test]
+    fn single_swap_impact_is_limited() {
+        let mut pool = breaker_pool(400, 0);
+        // ~1% passes quietly, ~4% executes as a strike and ~6% is rejected.
+        assert_eq!(check_swap(&mut pool, 1_005_000, 995_025, 100).unwrap(), None);
+        let strike = check_swap(&mut pool, 1_020_000, 980_392, 100).unwrap().unwrap();
+        assert!(strike.price_impact_bps > 200 && strike.price_impact_bps <= 400);
+        assert_eq!(pool.breaker_violations, 1);
+        assert!(check_swap(&mut pool, 1_030_000, 970_874, 100).is_err());
+        assert!(!pool.emergency_mode);
+    }
+
+    #[test]
+    fn window_move_accumulates_across_swaps() {
+        let mut pool = breaker_pool(0, 300);
+        // Three 1% steps inside one window stay under the 3% limit; the fourth crosses it.
+        for step in 0..4 {
+            let a = pool.reserve_a + 5_000;
+            let b = (pool.reserve_a as u128 * pool.reserve_b as u128 / a as u128) as u64;
+            let checked = check_swap(&mut pool, a, b, 100 + step);
+            if step < 3 {
+                checked.unwrap();
+                pool.set_reserves(a, b);
+            } else {
+                assert!(checked.is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_strikes_trip_emergency_mode() {
+        let mut pool = breaker_pool(0, 400);
+        for _ in 0..CIRCUIT_BREAKER_MAX_VIOLATIONS {
+            assert!(check_swap(&mut pool, 1_020_000, 980_392, 110).unwrap().is_some());
+        }
+        assert!(pool.emergency_mode);
+
+        // A fresh window measures from the then-current price again.
+        let later = 110 + CIRCUIT_BREAKER_WINDOW_SECONDS;
+        pool.emergency_mode = false;
+        pool.set_reserves(1_020_000, 980_392);
+        assert_eq!(check_swap(&mut pool, 1_025_000, 975_610, later).unwrap(), None);
+        assert_eq!(pool.breaker_violations, 0);
+    }
+
+    #[test]
+    fn disabled_breaker_ignores_moves() {
+        let mut pool = breaker_pool(0, 0);
+        assert_eq!(check_swap(&mut pool, 2_000_000, 500_000, 0).unwrap(), None);
+        assert_eq!(pool.window_start_price, 0);
+    }
+}
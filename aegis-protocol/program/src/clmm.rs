@@ -0,0 +1,1136 @@
+use anchor_lang::prelude::*;
+use std::ops::DerefMut;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    constants::{MAX_FEE_BPS, MAX_SWAP_TICK_ARRAYS, MAX_TICK, MAX_TICK_SPACING, MIN_TICK, TICK_ARRAY_SIZE},
+    errors::ErrorCode,
+    math::U256,
+    reentrancy, registry, seeds,
+    state::{
+        ClPool, ClPosition, CurveType, FeeTiers, PoolRegistry, PoolRegistryEntry, PoolRegistryPage, Tick,
+        TickArray,
+    },
+    tick_math, token_utils, validation,
+};
+
+#[event]
+pub struct ClPoolCreated {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee_bps: u16,
+    pub tick_spacing: u16,
+    pub sqrt_price_x64: u128,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct ClLiquidityChanged {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub liquidity_delta: i128,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub pool_liquidity: u128,
+}
+
+#[event]
+pub struct ClFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[event]
+pub struct ClSwapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+}
+
+#[event]
+pub struct ClEmergencyModeSet {
+    pub pool: Pubkey,
+    pub emergency_mode: bool,
+}
+
+#[derive(Accounts)]
+#[instruction(fee_bps: u16)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = payer,
+        space = ClPool::SIZE,
+        seeds = [b"cl_pool", mint_a.key().as_ref(), mint_b.key().as_ref(), fee_bps.to_le_bytes().as_ref()],
+        bump,
+        constraint = mint_a.key() < mint_b.key() @ ErrorCode::MintOrderInvalid
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_a,
+        token::authority = cl_pool,
+        token::token_program = token_program_a,
+        seeds = [b"pool_vault", cl_pool.key().as_ref(), mint_a.key().as_ref()],
+        bump
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_b,
+        token::authority = cl_pool,
+        token::token_program = token_program_b,
+        seeds = [b"pool_vault", cl_pool.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(seeds = [b"fee_tiers"], bump = fee_tiers.bump)]
+    pub fee_tiers: Box<Account<'info, FeeTiers>>,
+    #[account(mut, seeds = [b"pool_registry"], bump = registry.bump)]
+    pub registry: Box<Account<'info, PoolRegistry>>,
+    #[account(
+        mut,
+        seeds = [b"pool_registry_page", registry_page.page_index.to_le_bytes().as_ref()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
+    pub system_program: Program<'info, System>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(
+        init,
+        payer = payer,
+        space = TickArray::SIZE,
+        seeds = [b"tick_array", cl_pool.key().as_ref(), start_tick_index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub tick_array: Box<Account<'info, TickArray>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenClPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(
+        init,
+        payer = owner,
+        space = ClPosition::SIZE,
+        seeds = [
+            b"cl_position",
+            cl_pool.key().as_ref(),
+            owner.key().as_ref(),
+            tick_lower.to_le_bytes().as_ref(),
+            tick_upper.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// When both ticks live in the same tick array, pass that account as both
+/// `tick_array_lower` and `tick_array_upper`.
+#[derive(Accounts)]
+pub struct IncreaseClLiquidity<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized, has_one = cl_pool)]
+    pub position: Box<Account<'info, ClPosition>>,
+    #[account(mut, has_one = cl_pool @ ErrorCode::InvalidTickArray)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+    #[account(mut, has_one = cl_pool @ ErrorCode::InvalidTickArray)]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+    #[account(mut, address = cl_pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = cl_pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = cl_pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = cl_pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Removed liquidity is credited to `tokens_owed_*` and paid out by `collect_cl_fees`.
+#[derive(Accounts)]
+pub struct DecreaseClLiquidity<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized, has_one = cl_pool)]
+    pub position: Box<Account<'info, ClPosition>>,
+    #[account(mut, has_one = cl_pool @ ErrorCode::InvalidTickArray)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+    #[account(mut, has_one = cl_pool @ ErrorCode::InvalidTickArray)]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+}
+
+#[derive(Accounts)]
+pub struct CollectClFees<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(mut, has_one = owner @ ErrorCode::Unauthorized, has_one = cl_pool)]
+    pub position: Box<Account<'info, ClPosition>>,
+    #[account(mut, address = cl_pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = cl_pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = cl_pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = cl_pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Tick arrays are passed as remaining accounts (mut), up to `MAX_SWAP_TICK_ARRAYS`, starting
+/// with the array holding the current tick and ordered in the direction of the swap.
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+    #[account(mut, address = cl_pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = cl_pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = cl_pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = cl_pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub user_source: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetClEmergencyMode<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"cl_pool", cl_pool.mint_a.as_ref(), cl_pool.mint_b.as_ref(), cl_pool.fee_bps.to_le_bytes().as_ref()],
+        bump = cl_pool.bump,
+        has_one = creator @ ErrorCode::Unauthorized
+    )]
+    pub cl_pool: Box<Account<'info, ClPool>>,
+}
+
+pub fn initialize_cl_pool(
+    ctx: Context<InitializeClPool>,
+    fee_bps: u16,
+    tick_spacing: u16,
+    initial_sqrt_price_x64: u128,
+) -> Result<()> {
+    validation::assert_positive(fee_bps.into())?;
+    require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+    require!(ctx.accounts.fee_tiers.tiers.contains(&fee_bps), ErrorCode::FeeTierNotAllowed);
+    require!(tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING, ErrorCode::InvalidTick);
+    token_utils::assert_supported_mint(&ctx.accounts.mint_a)?;
+    token_utils::assert_supported_mint(&ctx.accounts.mint_b)?;
+    let tick_current = tick_math::tick_at_sqrt_price(initial_sqrt_price_x64)?;
+
+    let pool = &mut ctx.accounts.cl_pool;
+    pool.mint_a = ctx.accounts.mint_a.key();
+    pool.mint_b = ctx.accounts.mint_b.key();
+    pool.vault_a = ctx.accounts.vault_a.key();
+    pool.vault_b = ctx.accounts.vault_b.key();
+    pool.fee_bps = fee_bps;
+    pool.tick_spacing = tick_spacing;
+    pool.liquidity = 0;
+    pool.sqrt_price_x64 = initial_sqrt_price_x64;
+    pool.tick_current = tick_current;
+    pool.fee_growth_global_a_x64 = 0;
+    pool.fee_growth_global_b_x64 = 0;
+    pool.creator = ctx.accounts.payer.key();
+    pool.created_at = Clock::get()?.unix_timestamp;
+    pool.bump = ctx.bumps.cl_pool;
+    pool.emergency_mode = false;
+    pool.locked = false;
+    pool._reserved = [0; 30];
+    emit!(ClPoolCreated {
+        pool: pool.key(),
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        fee_bps,
+        tick_spacing,
+        sqrt_price_x64: initial_sqrt_price_x64,
+        creator: pool.creator,
+    });
+
+    let entry = PoolRegistryEntry {
+        pool: pool.key(),
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        fee_bps,
+        curve_type: CurveType::Concentrated,
+    };
+    registry::register_pool(&mut ctx.accounts.registry, &mut ctx.accounts.registry_page, entry)
+}
+
+pub fn initialize_tick_array(ctx: Context<InitializeTickArray>, start_tick_index: i32) -> Result<()> {
+    let ticks_per_array = ctx.accounts.cl_pool.tick_spacing as i32 * TICK_ARRAY_SIZE as i32;
+    require!(
+        start_tick_index.rem_euclid(ticks_per_array) == 0
+            && start_tick_index <= MAX_TICK
+            && start_tick_index + ticks_per_array > MIN_TICK,
+        ErrorCode::InvalidTickArray
+    );
+
+    let tick_array = &mut ctx.accounts.tick_array;
+    tick_array.cl_pool = ctx.accounts.cl_pool.key();
+    tick_array.start_tick_index = start_tick_index;
+    tick_array.bump = ctx.bumps.tick_array;
+    tick_array.ticks = [Tick::default(); TICK_ARRAY_SIZE];
+    Ok(())
+}
+
+pub fn open_cl_position(ctx: Context<OpenClPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+    let spacing = ctx.accounts.cl_pool.tick_spacing as i32;
+    require!(
+        tick_lower < tick_upper
+            && tick_lower >= MIN_TICK
+            && tick_upper <= MAX_TICK
+            && tick_lower % spacing == 0
+            && tick_upper % spacing == 0,
+        ErrorCode::InvalidTick
+    );
+
+    let position = &mut ctx.accounts.position;
+    position.cl_pool = ctx.accounts.cl_pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+    position.liquidity = 0;
+    position.fee_growth_inside_a_last_x64 = 0;
+    position.fee_growth_inside_b_last_x64 = 0;
+    position.tokens_owed_a = 0;
+    position.tokens_owed_b = 0;
+    position.bump = ctx.bumps.position;
+    Ok(())
+}
+
+pub fn increase_cl_liquidity(
+    ctx: Context<IncreaseClLiquidity>,
+    liquidity_delta: u128,
+    max_amount_a: u64,
+    max_amount_b: u64,
+) -> Result<()> {
+    require!(liquidity_delta > 0, ErrorCode::InvalidAmount);
+    let accounts = ctx.accounts;
+    validation::assert_cl_pool_not_emergency(&accounts.cl_pool)?;
+    reentrancy::lock(&mut *accounts.cl_pool, ctx.program_id)?;
+    update_position(accounts.as_mut(), liquidity_delta_i128(liquidity_delta)?)?;
+
+    let (amount_a, amount_b) = liquidity_amounts(&accounts.cl_pool, &accounts.position, liquidity_delta, true)?;
+    let gross_a = token_utils::gross_up_for_transfer_fee(&accounts.mint_a, amount_a)?;
+    let gross_b = token_utils::gross_up_for_transfer_fee(&accounts.mint_b, amount_b)?;
+    require!(gross_a <= max_amount_a && gross_b <= max_amount_b, ErrorCode::SlippageExceeded);
+
+    if gross_a > 0 {
+        let balance_before = accounts.vault_a.amount;
+        token_utils::transfer_checked(
+            accounts.token_program_a.to_account_info(),
+            accounts.user_token_a.to_account_info(),
+            &accounts.mint_a,
+            accounts.vault_a.to_account_info(),
+            accounts.owner.to_account_info(),
+            &[],
+            gross_a,
+        )?;
+        let received = token_utils::received_amount(&mut accounts.vault_a, balance_before)?;
+        require!(received >= amount_a, ErrorCode::SlippageExceeded);
+    }
+    if gross_b > 0 {
+        let balance_before = accounts.vault_b.amount;
+        token_utils::transfer_checked(
+            accounts.token_program_b.to_account_info(),
+            accounts.user_token_b.to_account_info(),
+            &accounts.mint_b,
+            accounts.vault_b.to_account_info(),
+            accounts.owner.to_account_info(),
+            &[],
+            gross_b,
+        )?;
+        let received = token_utils::received_amount(&mut accounts.vault_b, balance_before)?;
+        require!(received >= amount_b, ErrorCode::SlippageExceeded);
+    }
+
+    reentrancy::unlock(&mut *accounts.cl_pool);
+    emit!(ClLiquidityChanged {
+        pool: accounts.cl_pool.key(),
+        position: accounts.position.key(),
+        owner: accounts.owner.key(),
+        liquidity_delta: liquidity_delta_i128(liquidity_delta)?,
+        amount_a,
+        amount_b,
+        pool_liquidity: accounts.cl_pool.liquidity,
+    });
+    Ok(())
+}
+
+/// A zero `liquidity_delta` only accrues the position's fees.
+pub fn decrease_cl_liquidity(
+    ctx: Context<DecreaseClLiquidity>,
+    liquidity_delta: u128,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
+    let accounts = ctx.accounts;
+    reentrancy::assert_unlocked(&**accounts.cl_pool)?;
+    require!(accounts.position.liquidity > 0, ErrorCode::InsufficientLiquidity);
+    require!(liquidity_delta <= accounts.position.liquidity, ErrorCode::InsufficientLiquidity);
+    update_position(accounts.as_mut(), -liquidity_delta_i128(liquidity_delta)?)?;
+
+    let (amount_a, amount_b) = liquidity_amounts(&accounts.cl_pool, &accounts.position, liquidity_delta, false)?;
+    require!(amount_a >= min_amount_a && amount_b >= min_amount_b, ErrorCode::SlippageExceeded);
+    let position = &mut accounts.position;
+    position.tokens_owed_a = position
+        .tokens_owed_a
+        .checked_add(amount_a)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    position.tokens_owed_b = position
+        .tokens_owed_b
+        .checked_add(amount_b)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    emit!(ClLiquidityChanged {
+        pool: accounts.cl_pool.key(),
+        position: accounts.position.key(),
+        owner: accounts.owner.key(),
+        liquidity_delta: -liquidity_delta_i128(liquidity_delta)?,
+        amount_a,
+        amount_b,
+        pool_liquidity: accounts.cl_pool.liquidity,
+    });
+    Ok(())
+}
+
+/// Pays out everything owed to the position: accrued fees plus any withdrawn liquidity.
+pub fn collect_cl_fees(ctx: Context<CollectClFees>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let (amount_a, amount_b) = (accounts.position.tokens_owed_a, accounts.position.tokens_owed_b);
+    accounts.position.tokens_owed_a = 0;
+    accounts.position.tokens_owed_b = 0;
+    reentrancy::lock(&mut *accounts.cl_pool, ctx.program_id)?;
+
+    let (_bump_bytes, signer_seeds) = seeds::cl_pool_signer_seeds(&accounts.cl_pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    if amount_a > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_a.to_account_info(),
+            accounts.vault_a.to_account_info(),
+            &accounts.mint_a,
+            accounts.user_token_a.to_account_info(),
+            accounts.cl_pool.to_account_info(),
+            &[&signer_seeds_slice],
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_b.to_account_info(),
+            accounts.vault_b.to_account_info(),
+            &accounts.mint_b,
+            accounts.user_token_b.to_account_info(),
+            accounts.cl_pool.to_account_info(),
+            &[&signer_seeds_slice],
+            amount_b,
+        )?;
+    }
+
+    reentrancy::unlock(&mut *accounts.cl_pool);
+    emit!(ClFeesCollected {
+        pool: accounts.cl_pool.key(),
+        position: accounts.position.key(),
+        owner: accounts.owner.key(),
+        amount_a,
+        amount_b,
+    });
+    Ok(())
+}
+
+/// Exact-input swap that steps across initialized ticks. A zero `sqrt_price_limit_x64` means no
+/// limit; if the limit is reached first, the unspent input is refunded.
+pub fn cl_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+    sqrt_price_limit_x64: u128,
+    deadline: i64,
+) -> Result<()> {
+    validation::assert_positive(amount_in)?;
+    validation::assert_positive(min_amount_out)?;
+    validation::assert_deadline(deadline, Clock::get()?.unix_timestamp)?;
+
+    let accounts = ctx.accounts;
+    validation::assert_cl_pool_not_emergency(&accounts.cl_pool)?;
+    let (input_mint, output_mint) = if a_to_b {
+        (accounts.cl_pool.mint_a, accounts.cl_pool.mint_b)
+    } else {
+        (accounts.cl_pool.mint_b, accounts.cl_pool.mint_a)
+    };
+    require_keys_eq!(accounts.user_source.mint, input_mint, ErrorCode::InvalidVault);
+    require_keys_eq!(accounts.user_destination.mint, output_mint, ErrorCode::InvalidDestinationMint);
+
+    let sqrt_price_limit_x64 = swap_price_limit(&accounts.cl_pool, sqrt_price_limit_x64, a_to_b)?;
+    let mut tick_arrays = load_tick_arrays(ctx.remaining_accounts, accounts.cl_pool.key())?;
+
+    reentrancy::lock(&mut *accounts.cl_pool, ctx.program_id)?;
+    let received_in = transfer_cl_input(accounts, a_to_b, amount_in)?;
+    let (amount_remaining, amount_out) =
+        execute_cl_swap(&mut accounts.cl_pool, &mut tick_arrays, received_in, a_to_b, sqrt_price_limit_x64)?;
+    for tick_array in tick_arrays.iter() {
+        tick_array.exit(ctx.program_id)?;
+    }
+
+    if amount_remaining > 0 {
+        transfer_cl_output(accounts, !a_to_b, amount_remaining, true)?;
+    }
+    let delivered = transfer_cl_output(accounts, a_to_b, amount_out, false)?;
+    require!(delivered >= min_amount_out, ErrorCode::SlippageExceeded);
+
+    reentrancy::unlock(&mut *accounts.cl_pool);
+    let pool = &accounts.cl_pool;
+    emit!(ClSwapped {
+        pool: pool.key(),
+        user: accounts.user.key(),
+        a_to_b,
+        amount_in: received_in - amount_remaining,
+        amount_out: delivered,
+        sqrt_price_x64: pool.sqrt_price_x64,
+        tick_current: pool.tick_current,
+        liquidity: pool.liquidity,
+    });
+    Ok(())
+}
+
+/// Concentrated pools have no circuit breaker, so emergency mode is toggled by the creator alone.
+/// It halts swaps and new liquidity; withdrawals and fee collection stay open.
+pub fn set_cl_emergency_mode(ctx: Context<SetClEmergencyMode>, emergency_mode: bool) -> Result<()> {
+    let pool = &mut ctx.accounts.cl_pool;
+    pool.emergency_mode = emergency_mode;
+    emit!(ClEmergencyModeSet { pool: pool.key(), emergency_mode });
+    Ok(())
+}
+
+fn swap_price_limit(pool: &ClPool, sqrt_price_limit_x64: u128, a_to_b: bool) -> Result<u128> {
+    let min_sqrt_price = tick_math::sqrt_price_at_tick(MIN_TICK)?;
+    let max_sqrt_price = tick_math::sqrt_price_at_tick(MAX_TICK)?;
+    if sqrt_price_limit_x64 == 0 {
+        return Ok(if a_to_b { min_sqrt_price } else { max_sqrt_price });
+    }
+    if a_to_b {
+        require!(
+            sqrt_price_limit_x64 >= min_sqrt_price && sqrt_price_limit_x64 < pool.sqrt_price_x64,
+            ErrorCode::InvalidTick
+        );
+    } else {
+        require!(
+            sqrt_price_limit_x64 <= max_sqrt_price && sqrt_price_limit_x64 > pool.sqrt_price_x64,
+            ErrorCode::InvalidTick
+        );
+    }
+    Ok(sqrt_price_limit_x64)
+}
+
+fn load_tick_arrays<'info>(
+    remaining_accounts: &'info [AccountInfo<'info>],
+    cl_pool: Pubkey,
+) -> Result<Vec<Account<'info, TickArray>>> {
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len() <= MAX_SWAP_TICK_ARRAYS,
+        ErrorCode::InvalidTickArray
+    );
+    let mut tick_arrays: Vec<Account<'info, TickArray>> = Vec::with_capacity(remaining_accounts.len());
+    for info in remaining_accounts {
+        require!(info.is_writable, ErrorCode::InvalidTickArray);
+        let tick_array: Account<'info, TickArray> = Account::try_from(info)?;
+        require_keys_eq!(tick_array.cl_pool, cl_pool, ErrorCode::InvalidTickArray);
+        require!(
+            tick_arrays.iter().all(|t| t.key() != tick_array.key()),
+            ErrorCode::InvalidTickArray
+        );
+        tick_arrays.push(tick_array);
+    }
+    Ok(tick_arrays)
+}
+
+/// Runs the swap against the pool's ticks, returning the unspent input and the output owed.
+fn execute_cl_swap<T: DerefMut<Target = TickArray>>(
+    pool: &mut ClPool,
+    tick_arrays: &mut [T],
+    amount_in: u64,
+    a_to_b: bool,
+    sqrt_price_limit_x64: u128,
+) -> Result<(u64, u64)> {
+    let mut amount_remaining = amount_in;
+    let mut amount_out = 0u64;
+    let mut array_index = 0;
+    require!(
+        tick_arrays[0].tick_offset(pool.tick_current, pool.tick_spacing).is_some(),
+        ErrorCode::InvalidTickArray
+    );
+
+    while amount_remaining > 0 && pool.sqrt_price_x64 != sqrt_price_limit_x64 {
+        let tick_array = &tick_arrays[array_index];
+        let (next_tick, initialized) = next_tick_in_array(tick_array, pool.tick_current, pool.tick_spacing, a_to_b);
+        let next_tick = next_tick.clamp(MIN_TICK, MAX_TICK);
+        let next_sqrt_price = tick_math::sqrt_price_at_tick(next_tick)?;
+        let target_sqrt_price = if a_to_b {
+            next_sqrt_price.max(sqrt_price_limit_x64)
+        } else {
+            next_sqrt_price.min(sqrt_price_limit_x64)
+        };
+
+        let step = tick_math::compute_swap_step(
+            pool.sqrt_price_x64,
+            target_sqrt_price,
+            pool.liquidity,
+            amount_remaining,
+            pool.fee_bps,
+            a_to_b,
+        )?;
+        amount_remaining -= step.amount_in + step.fee_amount;
+        amount_out = amount_out
+            .checked_add(step.amount_out)
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        if let Some(fee_growth) = ((step.fee_amount as u128) << 64).checked_div(pool.liquidity) {
+            if a_to_b {
+                pool.fee_growth_global_a_x64 = pool.fee_growth_global_a_x64.wrapping_add(fee_growth);
+            } else {
+                pool.fee_growth_global_b_x64 = pool.fee_growth_global_b_x64.wrapping_add(fee_growth);
+            }
+        }
+        pool.sqrt_price_x64 = step.sqrt_price_next_x64;
+
+        if pool.sqrt_price_x64 == next_sqrt_price {
+            if initialized {
+                let offset = tick_array_offset(&tick_arrays[array_index], next_tick, pool.tick_spacing)?;
+                let tick = &mut tick_arrays[array_index].ticks[offset];
+                let liquidity_net = cross_tick(tick, pool.fee_growth_global_a_x64, pool.fee_growth_global_b_x64);
+                let liquidity_delta = if a_to_b { -liquidity_net } else { liquidity_net };
+                pool.liquidity = apply_liquidity_delta(pool.liquidity, liquidity_delta)?;
+            }
+            pool.tick_current = if a_to_b { next_tick - 1 } else { next_tick };
+        } else {
+            pool.tick_current = tick_math::tick_at_sqrt_price(pool.sqrt_price_x64)?;
+        }
+
+        let swap_continues = amount_remaining > 0 && pool.sqrt_price_x64 != sqrt_price_limit_x64;
+        if swap_continues
+            && tick_arrays[array_index]
+                .tick_offset(pool.tick_current, pool.tick_spacing)
+                .is_none()
+        {
+            array_index += 1;
+            require!(
+                array_index < tick_arrays.len()
+                    && tick_arrays[array_index]
+                        .tick_offset(pool.tick_current, pool.tick_spacing)
+                        .is_some(),
+                ErrorCode::InvalidTickArray
+            );
+        }
+    }
+    Ok((amount_remaining, amount_out))
+}
+
+/// Next initialized tick in the swap direction within `tick_array`, or the array's edge if none.
+/// Swapping A for B searches at or below the current tick, B for A strictly above it.
+fn next_tick_in_array(tick_array: &TickArray, tick_current: i32, tick_spacing: u16, a_to_b: bool) -> (i32, bool) {
+    let spacing = tick_spacing as i32;
+    let start = tick_array.start_tick_index;
+    let current_offset = (tick_current - start).div_euclid(spacing);
+    if a_to_b {
+        let from = current_offset.min(TICK_ARRAY_SIZE as i32 - 1);
+        (0..=from)
+            .rev()
+            .find(|offset| tick_array.ticks[*offset as usize].initialized)
+            .map_or((start, false), |offset| (start + offset * spacing, true))
+    } else {
+        (current_offset.max(-1) + 1..TICK_ARRAY_SIZE as i32)
+            .find(|offset| tick_array.ticks[*offset as usize].initialized)
+            .map_or((start + TICK_ARRAY_SIZE as i32 * spacing, false), |offset| {
+                (start + offset * spacing, true)
+            })
+    }
+}
+
+/// Flips the tick's outside fee growth as the price crosses it and returns its `liquidity_net`.
+fn cross_tick(tick: &mut Tick, fee_growth_global_a_x64: u128, fee_growth_global_b_x64: u128) -> i128 {
+    tick.fee_growth_outside_a_x64 = fee_growth_global_a_x64.wrapping_sub(tick.fee_growth_outside_a_x64);
+    tick.fee_growth_outside_b_x64 = fee_growth_global_b_x64.wrapping_sub(tick.fee_growth_outside_b_x64);
+    tick.liquidity_net
+}
+
+/// Accounts shared by the liquidity instructions, borrowed for `modify_position`.
+struct PositionAccounts<'a> {
+    pool: &'a mut ClPool,
+    position: &'a mut ClPosition,
+    tick_array_lower: &'a mut TickArray,
+    tick_array_upper: &'a mut TickArray,
+}
+
+impl<'info> IncreaseClLiquidity<'info> {
+    fn as_mut(&mut self) -> PositionAccounts<'_> {
+        PositionAccounts {
+            pool: &mut self.cl_pool,
+            position: &mut self.position,
+            tick_array_lower: &mut self.tick_array_lower,
+            tick_array_upper: &mut self.tick_array_upper,
+        }
+    }
+}
+
+impl<'info> DecreaseClLiquidity<'info> {
+    fn as_mut(&mut self) -> PositionAccounts<'_> {
+        PositionAccounts {
+            pool: &mut self.cl_pool,
+            position: &mut self.position,
+            tick_array_lower: &mut self.tick_array_lower,
+            tick_array_upper: &mut self.tick_array_upper,
+        }
+    }
+}
+
+/// Applies `liquidity_delta` when both tick arrays may be the same account: the lower array is
+/// updated and then copied over the upper one, which is serialized last.
+fn update_position(accounts: PositionAccounts, liquidity_delta: i128) -> Result<()> {
+    let PositionAccounts { pool, position, tick_array_lower, tick_array_upper } = accounts;
+    if tick_array_lower.start_tick_index == tick_array_upper.start_tick_index {
+        modify_position(pool, position, tick_array_lower, None, liquidity_delta)?;
+        tick_array_upper.ticks = tick_array_lower.ticks;
+        Ok(())
+    } else {
+        modify_position(pool, position, tick_array_lower, Some(tick_array_upper), liquidity_delta)
+    }
+}
+
+/// Adds `liquidity_delta` to a position, updating both boundary ticks, the fees owed to the
+/// position and, if the range is active, the pool's in-range liquidity. `tick_array_upper` is
+/// `None` when both ticks live in `tick_array_lower`.
+fn modify_position(
+    pool: &mut ClPool,
+    position: &mut ClPosition,
+    tick_array_lower: &mut TickArray,
+    tick_array_upper: Option<&mut TickArray>,
+    liquidity_delta: i128,
+) -> Result<()> {
+    let lower_offset = tick_array_offset(tick_array_lower, position.tick_lower, pool.tick_spacing)?;
+    let (lower, upper) = match tick_array_upper {
+        Some(tick_array_upper) => {
+            let upper_offset = tick_array_offset(tick_array_upper, position.tick_upper, pool.tick_spacing)?;
+            (
+                &mut tick_array_lower.ticks[lower_offset],
+                &mut tick_array_upper.ticks[upper_offset],
+            )
+        }
+        None => {
+            let upper_offset = tick_array_offset(tick_array_lower, position.tick_upper, pool.tick_spacing)?;
+            // Ticks are ordered, so the lower tick always sits before the upper one
+            let (head, tail) = tick_array_lower.ticks.split_at_mut(upper_offset);
+            (&mut head[lower_offset], &mut tail[0])
+        }
+    };
+
+    let (global_a, global_b) = (pool.fee_growth_global_a_x64, pool.fee_growth_global_b_x64);
+    if liquidity_delta != 0 {
+        update_tick(lower, position.tick_lower, pool.tick_current, liquidity_delta, global_a, global_b, false)?;
+        update_tick(upper, position.tick_upper, pool.tick_current, liquidity_delta, global_a, global_b, true)?;
+    }
+
+    let (inside_a, inside_b) = fee_growth_inside(lower, upper, position, pool.tick_current, global_a, global_b);
+    let owed_a = fees_earned(inside_a, position.fee_growth_inside_a_last_x64, position.liquidity)?;
+    let owed_b = fees_earned(inside_b, position.fee_growth_inside_b_last_x64, position.liquidity)?;
+    position.tokens_owed_a = position
+        .tokens_owed_a
+        .checked_add(owed_a)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    position.tokens_owed_b = position
+        .tokens_owed_b
+        .checked_add(owed_b)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    position.fee_growth_inside_a_last_x64 = inside_a;
+    position.fee_growth_inside_b_last_x64 = inside_b;
+    position.liquidity = apply_liquidity_delta(position.liquidity, liquidity_delta)?;
+
+    // Ticks no longer referenced by any position are cleared so swaps skip them
+    if lower.liquidity_gross == 0 {
+        *lower = Tick::default();
+    }
+    if upper.liquidity_gross == 0 {
+        *upper = Tick::default();
+    }
+
+    if (position.tick_lower..position.tick_upper).contains(&pool.tick_current) {
+        pool.liquidity = apply_liquidity_delta(pool.liquidity, liquidity_delta)?;
+    }
+    Ok(())
+}
+
+fn update_tick(
+    tick: &mut Tick,
+    tick_index: i32,
+    tick_current: i32,
+    liquidity_delta: i128,
+    fee_growth_global_a_x64: u128,
+    fee_growth_global_b_x64: u128,
+    is_upper: bool,
+) -> Result<()> {
+    if tick.liquidity_gross == 0 {
+        // By convention all fee growth so far happened below a tick at or under the current price
+        if tick_index <= tick_current {
+            tick.fee_growth_outside_a_x64 = fee_growth_global_a_x64;
+            tick.fee_growth_outside_b_x64 = fee_growth_global_b_x64;
+        }
+        tick.initialized = true;
+    }
+    tick.liquidity_gross = apply_liquidity_delta(tick.liquidity_gross, liquidity_delta)?;
+    tick.liquidity_net = if is_upper {
+        tick.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        tick.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    Ok(())
+}
+
+fn fee_growth_inside(
+    lower: &Tick,
+    upper: &Tick,
+    position: &ClPosition,
+    tick_current: i32,
+    fee_growth_global_a_x64: u128,
+    fee_growth_global_b_x64: u128,
+) -> (u128, u128) {
+    let inside = |global: u128, lower_outside: u128, upper_outside: u128| {
+        let below = if tick_current >= position.tick_lower {
+            lower_outside
+        } else {
+            global.wrapping_sub(lower_outside)
+        };
+        let above = if tick_current < position.tick_upper {
+            upper_outside
+        } else {
+            global.wrapping_sub(upper_outside)
+        };
+        global.wrapping_sub(below).wrapping_sub(above)
+    };
+    (
+        inside(fee_growth_global_a_x64, lower.fee_growth_outside_a_x64, upper.fee_growth_outside_a_x64),
+        inside(fee_growth_global_b_x64, lower.fee_growth_outside_b_x64, upper.fee_growth_outside_b_x64),
+    )
+}
+
+fn fees_earned(fee_growth_inside_x64: u128, fee_growth_inside_last_x64: u128, liquidity: u128) -> Result<u64> {
+    let growth = fee_growth_inside_x64.wrapping_sub(fee_growth_inside_last_x64);
+    let earned = (U256::from(growth) * U256::from(liquidity)) >> 64;
+    require!(earned.bits() <= 64, ErrorCode::ArithmeticOverflow);
+    Ok(earned.as_u64())
+}
+
+/// Token amounts backing `liquidity` over the position's range at the current price.
+fn liquidity_amounts(pool: &ClPool, position: &ClPosition, liquidity: u128, round_up: bool) -> Result<(u64, u64)> {
+    let sqrt_price_lower = tick_math::sqrt_price_at_tick(position.tick_lower)?;
+    let sqrt_price_upper = tick_math::sqrt_price_at_tick(position.tick_upper)?;
+    if pool.tick_current < position.tick_lower {
+        let amount_a = tick_math::amount_a_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+        Ok((amount_a, 0))
+    } else if pool.tick_current < position.tick_upper {
+        let amount_a = tick_math::amount_a_delta(pool.sqrt_price_x64, sqrt_price_upper, liquidity, round_up)?;
+        let amount_b = tick_math::amount_b_delta(sqrt_price_lower, pool.sqrt_price_x64, liquidity, round_up)?;
+        Ok((amount_a, amount_b))
+    } else {
+        let amount_b = tick_math::amount_b_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?;
+        Ok((0, amount_b))
+    }
+}
+
+fn tick_array_offset(tick_array: &TickArray, tick_index: i32, tick_spacing: u16) -> Result<usize> {
+    tick_array
+        .tick_offset(tick_index, tick_spacing)
+        .ok_or(error!(ErrorCode::InvalidTickArray))
+}
+
+fn apply_liquidity_delta(liquidity: u128, liquidity_delta: i128) -> Result<u128> {
+    if liquidity_delta >= 0 {
+        liquidity.checked_add(liquidity_delta as u128)
+    } else {
+        liquidity.checked_sub(liquidity_delta.unsigned_abs())
+    }
+    .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+fn liquidity_delta_i128(liquidity: u128) -> Result<i128> {
+    i128::try_from(liquidity).map_err(|_| error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Moves `amount_in` into the pool and returns how much the input vault actually received.
+fn transfer_cl_input(accounts: &mut ClSwap, a_to_b: bool, amount_in: u64) -> Result<u64> {
+    let (token_program, mint, input_vault) = if a_to_b {
+        (&accounts.token_program_a, &accounts.mint_a, &mut accounts.vault_a)
+    } else {
+        (&accounts.token_program_b, &accounts.mint_b, &mut accounts.vault_b)
+    };
+    let balance_before = input_vault.amount;
+    token_utils::transfer_checked(
+        token_program.to_account_info(),
+        accounts.user_source.to_account_info(),
+        mint,
+        input_vault.to_account_info(),
+        accounts.user.to_account_info(),
+        &[],
+        amount_in,
+    )?;
+    token_utils::received_amount(input_vault, balance_before)
+}
+
+/// Pays `amount` of the swap's output side (or, with `refund`, the input side back to
+/// `user_source`) and returns how much the user account actually received.
+fn transfer_cl_output(accounts: &mut ClSwap, a_to_b: bool, amount: u64, refund: bool) -> Result<u64> {
+    let (token_program, mint, vault) = if a_to_b {
+        (&accounts.token_program_b, &accounts.mint_b, &accounts.vault_b)
+    } else {
+        (&accounts.token_program_a, &accounts.mint_a, &accounts.vault_a)
+    };
+    let destination = if refund {
+        &mut accounts.user_source
+    } else {
+        &mut accounts.user_destination
+    };
+    let balance_before = destination.amount;
+    let (_bump_bytes, signer_seeds) = seeds::cl_pool_signer_seeds(&accounts.cl_pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token_utils::transfer_checked(
+        token_program.to_account_info(),
+        vault.to_account_info(),
+        mint,
+        destination.to_account_info(),
+        accounts.cl_pool.to_account_info(),
+        &[&signer_seeds_slice],
+        amount,
+    )?;
+    token_utils::received_amount(destination, balance_before)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACING: u16 = 10;
+
+    fn test_pool(tick_current: i32) -> ClPool {
+        ClPool {
+            mint_a: Pubkey::new_unique(),
+            mint_b: Pubkey::new_unique(),
+            vault_a: Pubkey::new_unique(),
+            vault_b: Pubkey::new_unique(),
+            fee_bps: 30,
+            tick_spacing: SPACING,
+            liquidity: 0,
+            sqrt_price_x64: tick_math::sqrt_price_at_tick(tick_current).unwrap(),
+            tick_current,
+            fee_growth_global_a_x64: 0,
+            fee_growth_global_b_x64: 0,
+            creator: Pubkey::new_unique(),
+            created_at: 0,
+            bump: 255,
+            emergency_mode: false,
+            locked: false,
+            _reserved: [0; 30],
+        }
+    }
+
+    fn test_tick_array(start_tick_index: i32) -> TickArray {
+        TickArray {
+            cl_pool: Pubkey::default(),
+            start_tick_index,
+            bump: 255,
+            ticks: [Tick::default(); TICK_ARRAY_SIZE],
+        }
+    }
+
+    fn test_position(tick_lower: i32, tick_upper: i32) -> ClPosition {
+        ClPosition {
+            cl_pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_a_last_x64: 0,
+            fee_growth_inside_b_last_x64: 0,
+            tokens_owed_a: 0,
+            tokens_owed_b: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn position_in_range_adds_pool_liquidity_and_ticks() {
+        let mut pool = test_pool(5);
+        let mut array = test_tick_array(0);
+        let mut position = test_position(0, 100);
+
+        modify_position(&mut pool, &mut position, &mut array, None, 1_000_000).unwrap();
+        assert_eq!(pool.liquidity, 1_000_000);
+        assert_eq!(position.liquidity, 1_000_000);
+        assert_eq!(array.ticks[0].liquidity_net, 1_000_000);
+        assert_eq!(array.ticks[10].liquidity_net, -1_000_000);
+
+        modify_position(&mut pool, &mut position, &mut array, None, -1_000_000).unwrap();
+        assert_eq!(pool.liquidity, 0);
+        assert!(!array.ticks[0].initialized && !array.ticks[10].initialized);
+    }
+
+    #[test]
+    fn position_out_of_range_leaves_pool_liquidity() {
+        let mut pool = test_pool(500);
+        let mut array = test_tick_array(0);
+        let mut position = test_position(0, 100);
+        modify_position(&mut pool, &mut position, &mut array, None, 1_000_000).unwrap();
+        assert_eq!(pool.liquidity, 0);
+        let (amount_a, amount_b) = liquidity_amounts(&pool, &position, 1_000_000, true).unwrap();
+        assert_eq!(amount_a, 0);
+        assert!(amount_b > 0);
+    }
+
+    #[test]
+    fn next_tick_search_respects_direction() {
+        let mut array = test_tick_array(0);
+        array.ticks[3].initialized = true;
+        array.ticks[7].initialized = true;
+
+        assert_eq!(next_tick_in_array(&array, 55, SPACING, true), (30, true));
+        assert_eq!(next_tick_in_array(&array, 30, SPACING, true), (30, true));
+        assert_eq!(next_tick_in_array(&array, 25, SPACING, true), (0, false));
+        assert_eq!(next_tick_in_array(&array, 30, SPACING, false), (70, true));
+        assert_eq!(next_tick_in_array(&array, 70, SPACING, false), (320, false));
+    }
+
+    #[test]
+    fn swap_crosses_tick_and_accrues_fees_to_range() {
+        // [-100, 100) spans both arrays around the price; [-300, -100) sits just below it
+        let mut pool = test_pool(0);
+        let mut arrays = vec![Box::new(test_tick_array(0)), Box::new(test_tick_array(-320))];
+        let mut inner = test_position(-100, 100);
+        let mut outer = test_position(-300, -100);
+        {
+            let (high, low) = arrays.split_at_mut(1);
+            modify_position(&mut pool, &mut inner, &mut low[0], Some(&mut high[0]), 10_000_000_000).unwrap();
+            modify_position(&mut pool, &mut outer, &mut low[0], None, 20_000_000_000).unwrap();
+        }
+        assert_eq!(pool.liquidity, 10_000_000_000);
+
+        // Sell enough A to push the price through tick -100 and into the outer range
+        let amount_in = 100_000_000u64;
+        let limit = tick_math::sqrt_price_at_tick(-250).unwrap();
+        let (remaining, out) = execute_cl_swap(&mut pool, &mut arrays, amount_in, true, limit).unwrap();
+        assert_eq!(remaining, 0);
+        assert!(out > 0 && out < amount_in);
+        assert!(pool.tick_current < -100 && pool.tick_current >= -250, "tick={}", pool.tick_current);
+        assert_eq!(pool.liquidity, 20_000_000_000);
+
+        // Both ranges earned fees; a zero-liquidity update accrues them
+        let (high, low) = arrays.split_at_mut(1);
+        modify_position(&mut pool, &mut inner, &mut low[0], Some(&mut high[0]), 0).unwrap();
+        modify_position(&mut pool, &mut outer, &mut low[0], None, 0).unwrap();
+        assert!(inner.tokens_owed_a > 0 && outer.tokens_owed_a > 0);
+        assert_eq!(inner.tokens_owed_b + outer.tokens_owed_b, 0);
+        let total_fees = inner.tokens_owed_a + outer.tokens_owed_a;
+        assert!(total_fees <= amount_in * 30 / 10_000 + 1, "fees={}", total_fees);
+        assert!(total_fees * 10_000 >= amount_in * 29, "fees={}", total_fees);
+    }
+
+    #[test]
+    fn swap_stops_at_price_limit_and_returns_remainder() {
+        let mut pool = test_pool(0);
+        let mut arrays = vec![Box::new(test_tick_array(0))];
+        let mut position = test_position(0, 300);
+        modify_position(&mut pool, &mut position, &mut arrays[0], None, 1_000_000_000).unwrap();
+
+        let limit = tick_math::sqrt_price_at_tick(50).unwrap();
+        let (remaining, out) = execute_cl_swap(&mut pool, &mut arrays, u32::MAX as u64, false, limit).unwrap();
+        assert_eq!(pool.sqrt_price_x64, limit);
+        assert_eq!(pool.tick_current, 50);
+        assert!(remaining > 0 && out > 0);
+    }
+
+    #[test]
+    fn swap_requires_array_for_next_range() {
+        let mut pool = test_pool(0);
+        let mut arrays = vec![Box::new(test_tick_array(0))];
+        let mut position = test_position(0, 300);
+        modify_position(&mut pool, &mut position, &mut arrays[0], None, 1_000_000_000).unwrap();
+
+        // Selling A moves below tick 0, which needs the array starting at -320
+        let limit = tick_math::sqrt_price_at_tick(-100).unwrap();
+        assert!(execute_cl_swap(&mut pool, &mut arrays, u32::MAX as u64, true, limit).is_err());
+    }
+}
@@ -52,9 +52,87 @@ pub const DEFAULT_MAX_STALENESS_SECONDS: i64 = 5 * 60;
 /// Oracle slippage basis points (0.5%)
 pub const ORACLE_SLIPPAGE_BPS: u16 = 50;
 
+/// Maximum number of pools a single `route_swap` may traverse
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+/// Remaining accounts per `route_swap` hop: pool, vaults, mints, token programs, oracle and observations
+pub const ROUTE_ACCOUNTS_PER_HOP: usize = 9;
+
+/// Bounds for the StableSwap amplification coefficient
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Newton iterations allowed when solving the StableSwap invariant
+pub const STABLE_SWAP_MAX_ITERATIONS: usize = 255;
+
+/// Bounds for token A's weight in a weighted pool (2% - 98%)
+pub const MIN_WEIGHT_BPS: u16 = 200;
+pub const MAX_WEIGHT_BPS: u16 = 9_800;
+
+/// Weighted pools reject trades above 30% of the relevant reserve to keep pow() precise
+pub const WEIGHTED_MAX_RATIO_BPS: u64 = 3_000;
+
+/// Relative error budget (WAD-scaled) added to weighted-pool powers so rounding favours the pool
+pub const WEIGHTED_POW_ERROR_WAD: u128 = 10_000;
+
+/// Bisection steps used to find the swapped share of a single-sided deposit (enough for any u64)
+pub const SINGLE_SIDED_SEARCH_ITERATIONS: usize = 64;
+
+/// Symbol given to every LP mint; the metadata name is `"AEGIS-LP <A>/<B>"`
+pub const LP_TOKEN_SYMBOL: &str = "AEGIS-LP";
+
+/// Metaplex limit on the metadata name length
+pub const MAX_LP_TOKEN_NAME_LEN: usize = 32;
+
+/// Fee tiers (bps) enabled when the fee tier list is first created
+pub const DEFAULT_FEE_TIERS: [u16; 4] = [1, 5, 30, 100];
+
+/// Maximum number of fee tiers the admin can enable
+pub const MAX_FEE_TIERS: usize = 16;
+
+/// Tick bounds for concentrated liquidity; sqrt(1.0001^tick) stays within Q64.64
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+
+/// Largest tick spacing a concentrated-liquidity pool may use
+pub const MAX_TICK_SPACING: u16 = 1_000;
+
+/// Initializable ticks held by one tick array account
+pub const TICK_ARRAY_SIZE: usize = 32;
+
+/// Tick arrays a single concentrated-liquidity swap may traverse
+pub const MAX_SWAP_TICK_ARRAYS: usize = 3;
+
+/// Flash loan fee (0.09%), left in the vaults for LPs
+pub const FLASH_LOAN_FEE_BPS: u64 = 9;
+
+/// Share of a filled limit order's output paid to the cranker (0.1%)
+pub const LIMIT_ORDER_CRANK_FEE_BPS: u64 = 10;
+
+/// Shortest interval allowed between DCA slices
+pub const MIN_DCA_INTERVAL_SECONDS: i64 = 60;
+
+/// Layout version written by `initialize_pool` and targeted by `migrate_pool`
+pub const POOL_VERSION: u8 = 3;
+
+/// Length of the window a pool's cumulative price move is measured over
+pub const CIRCUIT_BREAKER_WINDOW_SECONDS: i64 = 60;
+
+/// Breaker strikes within one window that put a pool into emergency mode
+pub const CIRCUIT_BREAKER_MAX_VIOLATIONS: u8 = 3;
+
+/// Share of a breaker limit, in basis points, past which an executed swap counts as a strike
+pub const CIRCUIT_BREAKER_STRIKE_THRESHOLD_BPS: u64 = 5_000;
+
+/// Pool feature flag: check swap output against the pool's `OracleConfig`
+pub const FEATURE_ORACLE_GUARD: u32 = 1 << 0;
+
 /// Pyth program ID
 pub const PYTH_PROGRAM_ID: &str = "FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2ep";
 
+/// Protocol admin allowed to create the fee tier list
+pub const PROTOCOL_ADMIN: &str = "EQ5c3ZTo33GFpB2JjCqga3ecnbv9cbRpGqnSYu4Dmyof";
+
 /// Emission admin public key
 pub const EMISSION_ADMIN: &str = "EQ5c3ZTo33GFpB2JjCqga3ecnbv9cbRpGqnSYu4Dmyof";
 
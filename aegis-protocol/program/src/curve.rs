@@ -0,0 +1,407 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{
+        BPS_DENOMINATOR, MAX_AMP, MAX_WEIGHT_BPS, MIN_AMP, MIN_WEIGHT_BPS, STABLE_SWAP_MAX_ITERATIONS,
+        WEIGHTED_MAX_RATIO_BPS, WEIGHTED_POW_ERROR_WAD,
+    },
+    errors::ErrorCode,
+    math::{self, u256_div_ceil, U256, WAD},
+    state::CurveType,
+};
+
+/// StableSwap is only implemented for two-token pools
+const N_COINS: u64 = 2;
+
+pub fn validate_curve(curve_type: CurveType, amp: u64, weight_a_bps: u16) -> Result<()> {
+    match curve_type {
+        CurveType::ConstantProduct => require!(amp == 0 && weight_a_bps == 0, ErrorCode::InvalidCurve),
+        CurveType::StableSwap => require!(
+            (MIN_AMP..=MAX_AMP).contains(&amp) && weight_a_bps == 0,
+            ErrorCode::InvalidCurve
+        ),
+        CurveType::Weighted => require!(
+            amp == 0 && (MIN_WEIGHT_BPS..=MAX_WEIGHT_BPS).contains(&weight_a_bps),
+            ErrorCode::InvalidCurve
+        ),
+        CurveType::Concentrated => return err!(ErrorCode::InvalidCurve),
+    }
+    Ok(())
+}
+
+/// Multipliers that bring both sides of a StableSwap pool to the same number of decimals.
+pub fn decimal_scales(decimals_in: u8, decimals_out: u8) -> Result<(u128, u128)> {
+    let max = decimals_in.max(decimals_out);
+    Ok((
+        math::pow10((max - decimals_in) as u32)? as u128,
+        math::pow10((max - decimals_out) as u32)? as u128,
+    ))
+}
+
+/// StableSwap invariant `D` for balances `x` and `y` (Curve's `get_D`), found with Newton's method.
+/// Inputs are u64 reserves scaled by at most 10^9 and `amp <= MAX_AMP`, so U256 never overflows.
+pub fn compute_d(x: u128, y: u128, amp: u64) -> Result<U256> {
+    let sum = U256::from(x) + U256::from(y);
+    if sum.is_zero() {
+        return Ok(U256::zero());
+    }
+    require!(x > 0 && y > 0, ErrorCode::InsufficientLiquidity);
+
+    let n = U256::from(N_COINS);
+    let ann = U256::from(amp) * n;
+    let mut d = sum;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let d_p = d * d / (U256::from(x) * n) * d / (U256::from(y) * n);
+        let d_prev = d;
+        d = (ann * sum + d_p * n) * d / ((ann - U256::one()) * d + (n + U256::one()) * d_p);
+        if abs_diff(d, d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+    err!(ErrorCode::CurveNotConverged)
+}
+
+/// Balance of the other token that keeps the invariant at `d` when one side holds `x` (Curve's `get_y`).
+pub fn compute_y(x: u128, d: U256, amp: u64) -> Result<U256> {
+    require!(x > 0, ErrorCode::InsufficientLiquidity);
+
+    let n = U256::from(N_COINS);
+    let ann = U256::from(amp) * n;
+    let x = U256::from(x);
+    let c = d * d / (x * n) * d / (ann * n);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let y_prev = y;
+        let denominator = (y * n + b)
+            .checked_sub(d)
+            .filter(|v| !v.is_zero())
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        y = (y * y + c) / denominator;
+        if abs_diff(y, y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+    err!(ErrorCode::CurveNotConverged)
+}
+
+/// Exact-input StableSwap quote. Rounds down, one unit in the pool's favour on top of Newton's tolerance.
+pub fn compute_stable_swap_out(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+    amp: u64,
+    scales: (u128, u128),
+) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+    let (scale_in, scale_out) = scales;
+
+    let amount_in_after_fee = (amount_in as u128)
+        .checked_mul((BPS_DENOMINATOR - fee_bps as u64) as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        / BPS_DENOMINATOR as u128;
+
+    let x = reserve_in * scale_in;
+    let y = reserve_out * scale_out;
+    let d = compute_d(x, y, amp)?;
+    let new_x = x
+        .checked_add(amount_in_after_fee * scale_in)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let new_y = compute_y(new_x, d, amp)?;
+
+    let dy = U256::from(y)
+        .checked_sub(new_y + U256::one())
+        .ok_or(error!(ErrorCode::ZeroAmountOut))?
+        / U256::from(scale_out);
+    require!(dy < U256::from(reserve_out), ErrorCode::InsufficientLiquidity);
+    let out_u64 = dy.as_u64();
+    require!(out_u64 > 0, ErrorCode::ZeroAmountOut);
+    Ok(out_u64)
+}
+
+/// Exact-output StableSwap quote: the smallest input that yields at least `amount_out`.
+pub fn compute_stable_swap_in(
+    amount_out: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+    amp: u64,
+    scales: (u128, u128),
+) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+    require!((fee_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidFee);
+    require!((amount_out as u128) < reserve_out, ErrorCode::InsufficientLiquidity);
+    let (scale_in, scale_out) = scales;
+
+    let x = reserve_in * scale_in;
+    let y = reserve_out * scale_out;
+    let d = compute_d(x, y, amp)?;
+    let new_y = y - amount_out as u128 * scale_out;
+    // Two units cover Newton's tolerance here and the extra unit withheld by `compute_stable_swap_out`
+    let new_x = compute_y(new_y, d, amp)? + U256::from(2u8);
+
+    let dx = new_x
+        .checked_sub(U256::from(x))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let amount_in_after_fee = u256_div_ceil(dx, U256::from(scale_in));
+    let amount_in = u256_div_ceil(
+        amount_in_after_fee * U256::from(BPS_DENOMINATOR),
+        U256::from(BPS_DENOMINATOR - fee_bps as u64),
+    );
+    require!(amount_in <= U256::from(u64::MAX), ErrorCode::ArithmeticOverflow);
+    let in_u64 = amount_in.as_u64();
+    require!(in_u64 > 0, ErrorCode::ZeroAmount);
+    Ok(in_u64)
+}
+
+/// Exact-input quote on the Balancer weighted product `x^w_in * y^w_out = k`:
+/// `out = reserve_out * (1 - (reserve_in / (reserve_in + in))^(w_in / w_out))`, rounded down.
+pub fn compute_weighted_swap_out(
+    amount_in: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+    weights: (u16, u16),
+) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+    require!(
+        amount_in as u128 <= reserve_in * WEIGHTED_MAX_RATIO_BPS as u128 / BPS_DENOMINATOR as u128,
+        ErrorCode::InsufficientLiquidity
+    );
+    let (weight_in, weight_out) = weights;
+
+    let amount_in_after_fee = (amount_in as u128)
+        .checked_mul((BPS_DENOMINATOR - fee_bps as u64) as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        / BPS_DENOMINATOR as u128;
+
+    // Base rounds up and the exponent down so the power, and therefore what the pool keeps, rounds up
+    let base = (reserve_in * WAD).div_ceil(reserve_in + amount_in_after_fee);
+    let exponent = weight_in as u128 * WAD / weight_out as u128;
+    let power = pow_with_error_margin(base, exponent)?;
+
+    let complement = WAD.saturating_sub(power);
+    let out = reserve_out * complement / WAD;
+    let out_u64: u64 = out.try_into().map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
+    require!(out_u64 > 0, ErrorCode::ZeroAmountOut);
+    Ok(out_u64)
+}
+
+/// Exact-output quote on the weighted product:
+/// `in = reserve_in * ((reserve_out / (reserve_out - out))^(w_out / w_in) - 1)`, rounded up.
+pub fn compute_weighted_swap_in(
+    amount_out: u64,
+    reserve_in: u128,
+    reserve_out: u128,
+    fee_bps: u16,
+    weights: (u16, u16),
+) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+    require!((fee_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidFee);
+    require!(
+        amount_out as u128 <= reserve_out * WEIGHTED_MAX_RATIO_BPS as u128 / BPS_DENOMINATOR as u128,
+        ErrorCode::InsufficientLiquidity
+    );
+    let (weight_in, weight_out) = weights;
+
+    let base = (reserve_out * WAD).div_ceil(reserve_out - amount_out as u128);
+    let exponent = (weight_out as u128 * WAD).div_ceil(weight_in as u128);
+    let power = pow_with_error_margin(base, exponent)?;
+
+    let ratio = power - WAD;
+    let amount_in_after_fee = u256_div_ceil(U256::from(reserve_in) * U256::from(ratio), U256::from(WAD));
+    let amount_in = u256_div_ceil(
+        amount_in_after_fee * U256::from(BPS_DENOMINATOR),
+        U256::from(BPS_DENOMINATOR - fee_bps as u64),
+    );
+    require!(amount_in <= U256::from(u64::MAX), ErrorCode::ArithmeticOverflow);
+    let in_u64 = amount_in.as_u64();
+    require!(in_u64 > 0, ErrorCode::ZeroAmount);
+    Ok(in_u64)
+}
+
+fn pow_with_error_margin(base: u128, exponent: u128) -> Result<u128> {
+    let power = math::pow_wad(base, exponent)?;
+    let margin = power / WAD * WEIGHTED_POW_ERROR_WAD + power % WAD * WEIGHTED_POW_ERROR_WAD / WAD + 1;
+    power
+        .checked_add(margin)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// xorshift64* so the fuzz cases are reproducible without extra dev-dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn range(&mut self, low: u64, high: u64) -> u64 {
+            low + self.next() % (high - low + 1)
+        }
+    }
+
+    const CASES: usize = 500;
+
+    #[test]
+    fn swap_never_decreases_invariant() {
+        let mut rng = Rng(0x5eed_0031);
+        let mut checked = 0;
+        for _ in 0..CASES {
+            let amp = rng.range(MIN_AMP, MAX_AMP);
+            let reserve_in = rng.range(1_000_000, 1_000_000_000_000_000) as u128;
+            let reserve_out = rng.range(1_000_000, 1_000_000_000_000_000) as u128;
+            let amount_in = rng.range(1_000, reserve_in as u64 / 2);
+            let fee_bps = rng.range(0, 100) as u16;
+
+            let Ok(amount_out) = compute_stable_swap_out(amount_in, reserve_in, reserve_out, fee_bps, amp, (1, 1))
+            else {
+                continue;
+            };
+            let d_before = compute_d(reserve_in, reserve_out, amp).unwrap();
+            let d_after = compute_d(reserve_in + amount_in as u128, reserve_out - amount_out as u128, amp).unwrap();
+            assert!(
+                d_after >= d_before,
+                "invariant decreased: amp={} reserves=({}, {}) in={} out={}",
+                amp, reserve_in, reserve_out, amount_in, amount_out
+            );
+            checked += 1;
+        }
+        assert!(checked > CASES / 2, "only {} cases exercised", checked);
+    }
+
+    #[test]
+    fn exact_out_delivers_requested_output() {
+        let mut rng = Rng(0x5eed_0032);
+        let mut checked = 0;
+        for _ in 0..CASES {
+            let amp = rng.range(MIN_AMP, MAX_AMP);
+            let reserve_in = rng.range(1_000_000, 1_000_000_000_000_000) as u128;
+            let reserve_out = rng.range(1_000_000, 1_000_000_000_000_000) as u128;
+            let amount_out = rng.range(1, reserve_out as u64 / 2);
+            let fee_bps = rng.range(0, 100) as u16;
+
+            let Ok(amount_in) = compute_stable_swap_in(amount_out, reserve_in, reserve_out, fee_bps, amp, (1, 1))
+            else {
+                continue;
+            };
+            let delivered =
+                compute_stable_swap_out(amount_in, reserve_in, reserve_out, fee_bps, amp, (1, 1)).unwrap();
+            assert!(delivered >= amount_out, "in={} out={} delivered={}", amount_in, amount_out, delivered);
+            checked += 1;
+        }
+        assert!(checked > CASES / 2, "only {} cases exercised", checked);
+    }
+
+    #[test]
+    fn balanced_pool_trades_near_parity() {
+        let reserve = 1_000_000_000_000u128;
+        let out = compute_stable_swap_out(1_000_000, reserve, reserve, 0, 100, (1, 1)).unwrap();
+        assert!((999_990..1_000_000).contains(&out), "out={}", out);
+    }
+
+    #[test]
+    fn decimals_are_normalised() {
+        let scales = decimal_scales(6, 9).unwrap();
+        assert_eq!(scales, (1_000, 1));
+        // 1 USDC (6 decimals) against a balanced pool of a 9-decimal stablecoin
+        let out = compute_stable_swap_out(1_000_000, 1_000_000_000_000, 1_000_000_000_000_000, 0, 100, scales).unwrap();
+        assert!((999_000_000..1_000_000_000).contains(&out), "out={}", out);
+    }
+
+    #[test]
+    fn rejects_invalid_curve_parameters() {
+        assert!(validate_curve(CurveType::StableSwap, 0, 0).is_err());
+        assert!(validate_curve(CurveType::StableSwap, MAX_AMP + 1, 0).is_err());
+        assert!(validate_curve(CurveType::ConstantProduct, 10, 0).is_err());
+        assert!(validate_curve(CurveType::StableSwap, 100, 0).is_ok());
+        assert!(validate_curve(CurveType::Weighted, 0, MIN_WEIGHT_BPS - 1).is_err());
+        assert!(validate_curve(CurveType::Weighted, 0, MAX_WEIGHT_BPS + 1).is_err());
+        assert!(validate_curve(CurveType::Weighted, 0, 8_000).is_ok());
+    }
+
+    #[test]
+    fn weighted_swap_never_decreases_invariant() {
+        let mut rng = Rng(0x5eed_0033);
+        let mut checked = 0;
+        for _ in 0..CASES {
+            let weight_in = rng.range(MIN_WEIGHT_BPS as u64, MAX_WEIGHT_BPS as u64) as u16;
+            let weights = (weight_in, BPS_DENOMINATOR as u16 - weight_in);
+            let reserve_in = rng.range(1_000_000, 1_000_000_000_000_000);
+            let reserve_out = rng.range(1_000_000, 1_000_000_000_000_000);
+            let amount_in = rng.range(1_000, reserve_in / 4);
+
+            let Ok(amount_out) =
+                compute_weighted_swap_out(amount_in, reserve_in as u128, reserve_out as u128, 0, weights)
+            else {
+                continue;
+            };
+            let before = math::weighted_geometric_mean(reserve_in, reserve_out, weights.0).unwrap();
+            let after =
+                math::weighted_geometric_mean(reserve_in + amount_in, reserve_out - amount_out, weights.0).unwrap();
+            // Both sides are themselves rounded down, so allow one unit of noise
+            assert!(
+                after + 1 >= before,
+                "invariant decreased: weights={:?} reserves=({}, {}) in={} out={}",
+                weights, reserve_in, reserve_out, amount_in, amount_out
+            );
+            checked += 1;
+        }
+        assert!(checked > CASES / 2, "only {} cases exercised", checked);
+    }
+
+    #[test]
+    fn weighted_exact_out_never_decreases_invariant() {
+        let mut rng = Rng(0x5eed_0034);
+        let mut checked = 0;
+        for _ in 0..CASES {
+            let weight_in = rng.range(MIN_WEIGHT_BPS as u64, MAX_WEIGHT_BPS as u64) as u16;
+            let weights = (weight_in, BPS_DENOMINATOR as u16 - weight_in);
+            let reserve_in = rng.range(1_000_000, 1_000_000_000_000_000);
+            let reserve_out = rng.range(1_000_000, 1_000_000_000_000_000);
+            let amount_out = rng.range(1, reserve_out / 4);
+
+            let Ok(amount_in) =
+                compute_weighted_swap_in(amount_out, reserve_in as u128, reserve_out as u128, 0, weights)
+            else {
+                continue;
+            };
+            let Some(new_reserve_in) = reserve_in.checked_add(amount_in) else {
+                continue;
+            };
+            let before = math::weighted_geometric_mean(reserve_in, reserve_out, weights.0).unwrap();
+            let after = math::weighted_geometric_mean(new_reserve_in, reserve_out - amount_out, weights.0).unwrap();
+            assert!(
+                after + 1 >= before,
+                "invariant decreased: weights={:?} reserves=({}, {}) in={} out={}",
+                weights, reserve_in, reserve_out, amount_in, amount_out
+            );
+            checked += 1;
+        }
+        assert!(checked > CASES / 4, "only {} cases exercised", checked);
+    }
+
+    #[test]
+    fn even_weights_match_constant_product() {
+        let out = compute_weighted_swap_out(1_000_000, 1_000_000_000, 2_000_000_000, 0, (5_000, 5_000)).unwrap();
+        // x*y=k gives 1_000_000 * 2e9 / 1_001_000_000 = 1_998_001
+        assert!((1_998_000..=1_998_001).contains(&out), "out={}", out);
+    }
+}
@@ -0,0 +1,314 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    constants::{BPS_DENOMINATOR, MIN_DCA_INTERVAL_SECONDS},
+    errors::ErrorCode,
+    oracle,
+    pool::EscrowSwap,
+    reentrancy, seeds,
+    state::{DcaOrder, OracleConfig, Pool, PoolObservations},
+    token_utils, validation,
+};
+
+#[event]
+pub struct DcaSliceExecuted {
+    pub order: Pubkey,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub min_amount_out: u64,
+    pub remaining: u64,
+}
+
+/// Input is escrowed in the same per-pool, per-mint escrow limit orders use.
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct OpenDcaOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = DcaOrder::SIZE,
+        seeds = [b"dca_order", pool.key().as_ref(), owner.key().as_ref(), order_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub order: Account<'info, DcaOrder>,
+    #[account(
+        mint::token_program = token_program,
+        constraint = input_mint.key() == pool.mint_a || input_mint.key() == pool.mint_b @ ErrorCode::InvalidVault
+    )]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), input_mint.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = input_mint)]
+    pub owner_source: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDcaOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = pool,
+        has_one = owner,
+        has_one = input_mint,
+        seeds = [b"dca_order", pool.key().as_ref(), owner.key().as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, DcaOrder>,
+    #[account(mint::token_program = token_program)]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), input_mint.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = input_mint)]
+    pub owner_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDcaSlice<'info> {
+    pub keeper: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        seeds = [b"dca_order", pool.key().as_ref(), owner.key().as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, DcaOrder>,
+    /// CHECK: order owner, receives the order account's rent once the last slice executes
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(seeds = [b"oracle", pool.key().as_ref()], bump = oracle_config.bump, has_one = pool)]
+    pub oracle_config: Account<'info, OracleConfig>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), order.input_mint.as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, constraint = owner_destination.owner == owner.key() @ ErrorCode::InvalidRecipient)]
+    pub owner_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Escrows `total_amount` to be sold `slice_amount` at a time; the first slice is due immediately.
+pub fn open_dca_order(
+    ctx: Context<OpenDcaOrder>,
+    order_id: u64,
+    total_amount: u64,
+    slice_amount: u64,
+    interval_seconds: i64,
+    max_slippage_bps: u16,
+) -> Result<()> {
+    validation::assert_positive(slice_amount)?;
+    require!(total_amount >= slice_amount, ErrorCode::InvalidAmount);
+    require!(interval_seconds >= MIN_DCA_INTERVAL_SECONDS, ErrorCode::InvalidAmount);
+    require!((max_slippage_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidAmount);
+
+    let accounts = ctx.accounts;
+    let balance_before = accounts.escrow.amount;
+    token_utils::transfer_checked(
+        accounts.token_program.to_account_info(),
+        accounts.owner_source.to_account_info(),
+        &accounts.input_mint,
+        accounts.escrow.to_account_info(),
+        accounts.owner.to_account_info(),
+        &[],
+        total_amount,
+    )?;
+    let received = token_utils::received_amount(&mut accounts.escrow, balance_before)?;
+
+    let order = &mut accounts.order;
+    order.pool = accounts.pool.key();
+    order.owner = accounts.owner.key();
+    order.order_id = order_id;
+    order.input_mint = accounts.input_mint.key();
+    order.a_to_b = order.input_mint == accounts.pool.mint_a;
+    order.remaining = received;
+    order.slice_amount = slice_amount;
+    order.interval_seconds = interval_seconds;
+    order.max_slippage_bps = max_slippage_bps;
+    order.last_executed_at = 0;
+    order.slices_executed = 0;
+    order.created_at = Clock::get()?.unix_timestamp;
+    order.bump = ctx.bumps.order;
+    Ok(())
+}
+
+/// Returns whatever is left in escrow to the owner and closes the order.
+pub fn close_dca_order(ctx: Context<CloseDcaOrder>) -> Result<()> {
+    let accounts = ctx.accounts;
+    if accounts.order.remaining == 0 {
+        return Ok(());
+    }
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token_utils::transfer_checked(
+        accounts.token_program.to_account_info(),
+        accounts.escrow.to_account_info(),
+        &accounts.input_mint,
+        accounts.owner_destination.to_account_info(),
+        accounts.pool.to_account_info(),
+        &[&signer_seeds_slice],
+        accounts.order.remaining,
+    )?;
+    reentrancy::unlock(&mut accounts.pool);
+    Ok(())
+}
+
+/// Keeper crank: sells the next slice through the pool once `interval_seconds` has passed since
+/// the previous one, failing if the owner receives less than the oracle price minus `max_slippage_bps`.
+/// The order closes itself after the last slice.
+pub fn execute_dca_slice(ctx: Context<ExecuteDcaSlice>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = ctx.accounts;
+    let order = &accounts.order;
+    require!(order.remaining > 0, ErrorCode::InvalidAmount);
+    require!(slice_due(order, now), ErrorCode::DcaIntervalNotElapsed);
+    validation::assert_no_flash_loan(&accounts.pool)?;
+    let oracle_config = &accounts.oracle_config;
+    validation::assert_staleness(oracle_config.max_staleness_seconds, now, oracle_config.last_updated_ts)?;
+
+    let a_to_b = order.a_to_b;
+    let output_mint = if a_to_b { accounts.pool.mint_b } else { accounts.pool.mint_a };
+    require_keys_eq!(accounts.owner_destination.mint, output_mint, ErrorCode::InvalidDestinationMint);
+
+    let amount_in = order.slice_amount.min(order.remaining);
+    let (decimals_in, decimals_out) = if a_to_b {
+        (accounts.pool.decimals_a, accounts.pool.decimals_b)
+    } else {
+        (accounts.pool.decimals_b, accounts.pool.decimals_a)
+    };
+
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+    let mut escrow_swap = EscrowSwap {
+        pool: &mut accounts.pool,
+        escrow: &accounts.escrow,
+        vault_a: &mut accounts.vault_a,
+        vault_b: &mut accounts.vault_b,
+        mint_a: &accounts.mint_a,
+        mint_b: &accounts.mint_b,
+        token_program_a: &accounts.token_program_a,
+        token_program_b: &accounts.token_program_b,
+        oracle_config: Some(&accounts.oracle_config),
+        observations: accounts.observations.as_deref_mut().map(|observations| &mut **observations),
+    };
+    let (received_in, pool_out) = escrow_swap.execute(accounts.owner.key(), a_to_b, amount_in, now)?;
+    // The bound is priced on what the pool took in, so an input transfer fee is not held against
+    // it; the owner is held to what they actually received, so an output fee is
+    let min_amount_out = oracle::compute_expected_out_from_oracle(
+        oracle_config,
+        received_in,
+        decimals_in,
+        decimals_out,
+        a_to_b,
+        order.max_slippage_bps,
+    )?;
+    let amount_out = escrow_swap.pay_out(a_to_b, &mut accounts.owner_destination, pool_out)?;
+    require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+    reentrancy::unlock(&mut accounts.pool);
+
+    let order = &mut accounts.order;
+    order.remaining -= amount_in;
+    order.last_executed_at = now;
+    order.slices_executed = order
+        .slices_executed
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+
+    emit!(DcaSliceExecuted {
+        order: order.key(),
+        pool: order.pool,
+        owner: order.owner,
+        amount_in,
+        amount_out,
+        min_amount_out,
+        remaining: order.remaining,
+    });
+    if order.remaining == 0 {
+        accounts.order.close(accounts.owner.to_account_info())?;
+    }
+    Ok(())
+}
+
+fn slice_due(order: &DcaOrder, now: i64) -> bool {
+    order.last_executed_at == 0 || now >= order.last_executed_at.saturating_add(order.interval_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_are_spaced_by_interval() {
+        let mut order = DcaOrder {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            order_id: 0,
+            input_mint: Pubkey::default(),
+            a_to_b: true,
+            remaining: 1_000,
+            slice_amount: 100,
+            interval_seconds: 3_600,
+            max_slippage_bps: 50,
+            last_executed_at: 0,
+            slices_executed: 0,
+            created_at: 1_000,
+            bump: 0,
+        };
+        assert!(slice_due(&order, 1_000));
+        order.last_executed_at = 1_000;
+        assert!(!slice_due(&order, 4_599));
+        assert!(slice_due(&order, 4_600));
+    }
+}
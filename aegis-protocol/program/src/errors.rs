@@ -106,4 +106,76 @@ pub enum ErrorCode {
 
     #[msg("Reentrancy detected")]
     ReentrancyDetected,
-}
\ No newline at end of file
+
+    #[msg("Transaction deadline has passed")]
+    DeadlineExceeded,
+
+    #[msg("Destination token account not owned by recipient")]
+    InvalidRecipient,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("Swap output deviates from oracle price")]
+    OracleDeviationExceeded,
+
+    #[msg("Invalid swap route")]
+    InvalidRoute,
+
+    #[msg("Invalid curve parameters")]
+    InvalidCurve,
+
+    #[msg("Curve invariant did not converge")]
+    CurveNotConverged,
+
+    #[msg("Mint has an unsupported Token-2022 extension")]
+    UnsupportedMintExtension,
+
+    #[msg("Invalid LP token metadata")]
+    InvalidLpMetadata,
+
+    #[msg("Pool registry page is full")]
+    RegistryPageFull,
+
+    #[msg("Registry page is not the current page")]
+    InvalidRegistryPage,
+
+    #[msg("Fee tier is not enabled")]
+    FeeTierNotAllowed,
+
+    #[msg("Invalid tick or tick range")]
+    InvalidTick,
+
+    #[msg("Missing or mismatched tick array")]
+    InvalidTickArray,
+
+    #[msg("Flash loan in progress")]
+    FlashLoanActive,
+
+    #[msg("Flash loan not repaid in this transaction")]
+    FlashLoanNotRepaid,
+
+    #[msg("Limit order price not reached")]
+    OrderNotFillable,
+
+    #[msg("Limit order has expired")]
+    OrderExpired,
+
+    #[msg("DCA slice interval has not elapsed")]
+    DcaIntervalNotElapsed,
+
+    #[msg("No LP tokens are staked in this pool")]
+    NoStakedLiquidity,
+
+    #[msg("Pool is already on the current layout")]
+    PoolAlreadyMigrated,
+
+    #[msg("Pool account data does not match a known layout")]
+    InvalidPoolLayout,
+
+    #[msg("Pool is in emergency mode")]
+    PoolInEmergencyMode,
+
+    #[msg("Swap moves the price past the pool's circuit breaker limits")]
+    CircuitBreakerTripped,
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use std::str::FromStr;
+
+use crate::{
+    constants::{DEFAULT_FEE_TIERS, MAX_FEE_BPS, MAX_FEE_TIERS, PROTOCOL_ADMIN},
+    errors::ErrorCode,
+    state::FeeTiers,
+};
+
+#[derive(Accounts)]
+pub struct InitializeFeeTiers<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = FeeTiers::SIZE,
+        seeds = [b"fee_tiers"],
+        bump
+    )]
+    pub fee_tiers: Account<'info, FeeTiers>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeTiers<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"fee_tiers"],
+        bump = fee_tiers.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub fee_tiers: Account<'info, FeeTiers>,
+}
+
+pub fn initialize_fee_tiers(ctx: Context<InitializeFeeTiers>) -> Result<()> {
+    let protocol_admin = Pubkey::from_str(PROTOCOL_ADMIN).map_err(|_| error!(ErrorCode::Unauthorized))?;
+    require_keys_eq!(ctx.accounts.admin.key(), protocol_admin, ErrorCode::Unauthorized);
+
+    let fee_tiers = &mut ctx.accounts.fee_tiers;
+    fee_tiers.admin = protocol_admin;
+    fee_tiers.bump = ctx.bumps.fee_tiers;
+    fee_tiers.tiers = DEFAULT_FEE_TIERS.to_vec();
+    Ok(())
+}
+
+pub fn add_fee_tier(ctx: Context<UpdateFeeTiers>, fee_bps: u16) -> Result<()> {
+    require!(fee_bps > 0 && fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+    let tiers = &mut ctx.accounts.fee_tiers.tiers;
+    require!(!tiers.contains(&fee_bps), ErrorCode::InvalidFee);
+    require!(tiers.len() < MAX_FEE_TIERS, ErrorCode::InvalidFee);
+    tiers.push(fee_bps);
+    Ok(())
+}
+
+/// Disabling a tier only stops new pools; existing pools at that tier keep trading.
+pub fn remove_fee_tier(ctx: Context<UpdateFeeTiers>, fee_bps: u16) -> Result<()> {
+    let tiers = &mut ctx.accounts.fee_tiers.tiers;
+    let position = tiers
+        .iter()
+        .position(|tier| *tier == fee_bps)
+        .ok_or(error!(ErrorCode::InvalidFee))?;
+    tiers.remove(position);
+    Ok(())
+}
+
+pub fn set_fee_tier_admin(ctx: Context<UpdateFeeTiers>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.fee_tiers.admin = new_admin;
+    Ok(())
+}
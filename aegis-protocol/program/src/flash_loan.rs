@@ -0,0 +1,279 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{self as instructions_sysvar, load_instruction_at_checked};
+use anchor_lang::Discriminator;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    constants::{BPS_DENOMINATOR, FLASH_LOAN_FEE_BPS},
+    errors::ErrorCode,
+    math, reentrancy, seeds,
+    state::Pool,
+    token_utils, validation,
+};
+
+/// Index of `pool` in `FlashRepay`'s account list, used when scanning for the repayment.
+const FLASH_REPAY_POOL_INDEX: usize = 1;
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// CHECK: instructions sysvar, scanned for the matching `flash_repay`
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut)]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Lends from the pool's vaults. A `flash_repay` for the same pool must appear later in the
+/// same transaction; it restores each vault to its pre-loan balance plus `FLASH_LOAN_FEE_BPS`.
+pub fn flash_loan(ctx: Context<FlashLoan>, amount_a: u64, amount_b: u64) -> Result<()> {
+    require!(amount_a > 0 || amount_b > 0, ErrorCode::InvalidAmount);
+    validation::assert_no_flash_loan(&ctx.accounts.pool)?;
+    validation::assert_not_emergency(&ctx.accounts.pool)?;
+    require!(
+        amount_a <= ctx.accounts.vault_a.amount && amount_b <= ctx.accounts.vault_b.amount,
+        ErrorCode::InsufficientLiquidity
+    );
+    assert_repaid_later(&ctx.accounts.instructions, ctx.accounts.pool.key())?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let (fee_a, fee_b) = (flash_loan_fee(amount_a), flash_loan_fee(amount_b));
+    let repay_a = math::add_u64(ctx.accounts.vault_a.amount, fee_a)?;
+    let repay_b = math::add_u64(ctx.accounts.vault_b.amount, fee_b)?;
+
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&ctx.accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    if amount_a > 0 {
+        token_utils::transfer_checked(
+            ctx.accounts.token_program_a.to_account_info(),
+            ctx.accounts.vault_a.to_account_info(),
+            &ctx.accounts.mint_a,
+            ctx.accounts.user_token_a.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            &[&signer_seeds_slice],
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        token_utils::transfer_checked(
+            ctx.accounts.token_program_b.to_account_info(),
+            ctx.accounts.vault_b.to_account_info(),
+            &ctx.accounts.mint_b,
+            ctx.accounts.user_token_b.to_account_info(),
+            ctx.accounts.pool.to_account_info(),
+            &[&signer_seeds_slice],
+            amount_b,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.flash_loan_active = true;
+    pool.flash_repay_a = repay_a;
+    pool.flash_repay_b = repay_b;
+    // Booked up front: the transaction reverts unless `flash_repay` brings the fee in
+    let (reserve_a, reserve_b) = (math::add_u64(pool.reserve_a, fee_a)?, math::add_u64(pool.reserve_b, fee_b)?);
+    pool.set_reserves(reserve_a, reserve_b);
+    reentrancy::unlock(pool);
+    Ok(())
+}
+
+/// Pulls whatever is still missing from the user and closes the loan. The fee stays in the
+/// vaults, raising reserves for every LP.
+pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+    require!(ctx.accounts.pool.flash_loan_active, ErrorCode::FlashLoanNotRepaid);
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let accounts = ctx.accounts;
+    let gross_a = repay_amount(accounts.pool.flash_repay_a, accounts.vault_a.amount, |shortfall| {
+        token_utils::gross_up_for_transfer_fee(&accounts.mint_a, shortfall)
+    })?;
+    if gross_a > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_a.to_account_info(),
+            accounts.user_token_a.to_account_info(),
+            &accounts.mint_a,
+            accounts.vault_a.to_account_info(),
+            accounts.user.to_account_info(),
+            &[],
+            gross_a,
+        )?;
+        accounts.vault_a.reload()?;
+    }
+    let gross_b = repay_amount(accounts.pool.flash_repay_b, accounts.vault_b.amount, |shortfall| {
+        token_utils::gross_up_for_transfer_fee(&accounts.mint_b, shortfall)
+    })?;
+    if gross_b > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_b.to_account_info(),
+            accounts.user_token_b.to_account_info(),
+            &accounts.mint_b,
+            accounts.vault_b.to_account_info(),
+            accounts.user.to_account_info(),
+            &[],
+            gross_b,
+        )?;
+        accounts.vault_b.reload()?;
+    }
+    require!(
+        accounts.vault_a.amount >= accounts.pool.flash_repay_a
+            && accounts.vault_b.amount >= accounts.pool.flash_repay_b,
+        ErrorCode::FlashLoanNotRepaid
+    );
+
+    let pool = &mut accounts.pool;
+    pool.flash_loan_active = false;
+    pool.flash_repay_a = 0;
+    pool.flash_repay_b = 0;
+    reentrancy::unlock(pool);
+    Ok(())
+}
+
+/// Fee owed on top of `amount`, rounded up.
+fn flash_loan_fee(amount: u64) -> u64 {
+    (amount as u128 * FLASH_LOAN_FEE_BPS as u128).div_ceil(BPS_DENOMINATOR as u128) as u64
+}
+
+/// What the user must send so the vault is back at `owed`; `gross_up` adds the mint's transfer fee.
+fn repay_amount(owed: u64, vault_balance: u64, gross_up: impl FnOnce(u64) -> Result<u64>) -> Result<u64> {
+    let shortfall = owed.saturating_sub(vault_balance);
+    if shortfall == 0 {
+        return Ok(0);
+    }
+    gross_up(shortfall)
+}
+
+/// Requires a top-level `flash_repay` for `pool` after the currently executing instruction.
+fn assert_repaid_later(instructions: &AccountInfo, pool: Pubkey) -> Result<()> {
+    let current_index = instructions_sysvar::load_current_index_checked(instructions)? as usize;
+    let mut index = current_index + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        let is_repay = instruction.program_id == crate::ID
+            && instruction.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+            && instruction
+                .accounts
+                .get(FLASH_REPAY_POOL_INDEX)
+                .is_some_and(|meta| meta.pubkey == pool);
+        if is_repay {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(ErrorCode::FlashLoanNotRepaid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::sysvar::instructions::{
+        construct_instructions_data, BorrowedAccountMeta, BorrowedInstruction,
+    };
+
+    #[test]
+    fn flash_loan_fee_rounds_up() {
+        assert_eq!(flash_loan_fee(1_000_000), 900);
+        assert_eq!(flash_loan_fee(1), 1);
+        assert_eq!(flash_loan_fee(0), 0);
+    }
+
+    #[test]
+    fn repay_amount_grosses_up_only_the_shortfall() {
+        // 1% transfer fee, rounded up so the net amount covers the shortfall
+        let one_percent_fee = |net: u64| Ok(net + (net * 100).div_ceil(9_900));
+        assert_eq!(repay_amount(1_000_900, 1_000_000, one_percent_fee).unwrap(), 910);
+        assert_eq!(repay_amount(1_000_900, 1_000_900, |_| panic!("nothing to gross up")).unwrap(), 0);
+        assert_eq!(repay_amount(1_000_900, 2_000_000, |_| panic!("nothing to gross up")).unwrap(), 0);
+    }
+
+    /// Instructions sysvar data for `(program_id, discriminator, accounts)` entries, with
+    /// `current_index` as the executing instruction.
+    fn instructions_sysvar_data(instructions: &[(Pubkey, &[u8], Vec<Pubkey>)], current_index: u16) -> Vec<u8> {
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|(program_id, data, accounts)| BorrowedInstruction {
+                program_id,
+                accounts: accounts
+                    .iter()
+                    .map(|pubkey| BorrowedAccountMeta { pubkey, is_signer: false, is_writable: true })
+                    .collect(),
+                data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        let len = data.len();
+        data[len - 2..].copy_from_slice(&current_index.to_le_bytes());
+        data
+    }
+
+    fn check_repaid_later(mut data: Vec<u8>, pool: Pubkey) -> Result<()> {
+        let (key, owner, mut lamports) = (instructions_sysvar::ID, Pubkey::default(), 0);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert_repaid_later(&info, pool)
+    }
+
+    #[test]
+    fn assert_repaid_later_matches_pool_at_repay_account_index() {
+        let (user, pool, other_pool) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let loan = (crate::ID, crate::instruction::FlashLoan::DISCRIMINATOR, vec![user, pool]);
+        let repay = |pool| (crate::ID, crate::instruction::FlashRepay::DISCRIMINATOR, vec![user, pool]);
+
+        let data = instructions_sysvar_data(&[loan.clone(), repay(pool)], 0);
+        assert!(check_repaid_later(data, pool).is_ok());
+        // Repays a different pool
+        let data = instructions_sysvar_data(&[loan.clone(), repay(other_pool)], 0);
+        assert!(check_repaid_later(data, pool).is_err());
+        // Pool passed at the wrong position
+        let swapped = (crate::ID, crate::instruction::FlashRepay::DISCRIMINATOR, vec![pool, user]);
+        let data = instructions_sysvar_data(&[loan.clone(), swapped], 0);
+        assert!(check_repaid_later(data, pool).is_err());
+        // The only repay runs before the loan
+        let data = instructions_sysvar_data(&[repay(pool), loan.clone()], 1);
+        assert!(check_repaid_later(data, pool).is_err());
+        // Same discriminator, another program
+        let foreign = (Pubkey::new_unique(), crate::instruction::FlashRepay::DISCRIMINATOR, vec![user, pool]);
+        let data = instructions_sysvar_data(&[loan, foreign], 0);
+        assert!(check_repaid_later(data, pool).is_err());
+    }
+}
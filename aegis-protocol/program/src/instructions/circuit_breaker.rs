@@ -0,0 +1 @@
+pub use crate::circuit_breaker::*;
\ No newline at end of file
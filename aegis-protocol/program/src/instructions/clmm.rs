@@ -0,0 +1 @@
+pub use crate::clmm::*;
\ No newline at end of file
@@ -0,0 +1 @@
+pub use crate::dca::*;
\ No newline at end of file
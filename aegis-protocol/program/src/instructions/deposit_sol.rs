@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::system_instruction;
+use anchor_lang::system_program;
 use crate::state::*;
 
 /// Instruction: Deposit SOL into the vault
@@ -37,18 +37,15 @@ pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64) -> Result<()> {
     let owner = &ctx.accounts.owner;
 
     // Transfer SOL from owner to vault
-    let transfer_ix = system_instruction::transfer(
-        &owner.key(),
-        &vault.key(),
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: owner.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
         amount,
-    );
-
-    anchor_lang::solana_program::program::invoke(
-        &transfer_ix,
-        &[
-            owner.to_account_info(),
-            vault.to_account_info(),
-        ],
     )?;
 
     // Update vault balance
@@ -0,0 +1 @@
+pub use crate::fee_tiers::*;
\ No newline at end of file
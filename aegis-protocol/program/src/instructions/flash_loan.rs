@@ -0,0 +1 @@
+pub use crate::flash_loan::*;
\ No newline at end of file
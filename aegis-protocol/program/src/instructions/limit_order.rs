@@ -0,0 +1 @@
+pub use crate::limit_order::*;
\ No newline at end of file
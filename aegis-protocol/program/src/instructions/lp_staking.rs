@@ -0,0 +1 @@
+pub use crate::lp_staking::*;
\ No newline at end of file
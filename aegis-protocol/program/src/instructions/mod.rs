@@ -1,4 +1,16 @@
 pub mod pool;
+pub mod oracle;
+pub mod circuit_breaker;
+pub mod twap;
+pub mod registry;
+pub mod fee_tiers;
+pub mod clmm;
+pub mod flash_loan;
+pub mod limit_order;
+pub mod dca;
+pub mod lp_staking;
+pub mod quote;
+pub mod reserves;
 pub mod initialize_vault;
 pub mod deposit_sol;
 pub mod approve_pending_action;
@@ -6,6 +18,18 @@ pub mod request_swap_jupiter;
 pub mod tokenomics;
 
 pub use pool::*;
+pub use oracle::*;
+pub use circuit_breaker::*;
+pub use twap::*;
+pub use registry::*;
+pub use fee_tiers::*;
+pub use clmm::*;
+pub use flash_loan::*;
+pub use limit_order::*;
+pub use dca::*;
+pub use lp_staking::*;
+pub use quote::*;
+pub use reserves::*;
 pub use initialize_vault::*;
 pub use deposit_sol::*;
 pub use approve_pending_action::*;
@@ -0,0 +1 @@
+pub use crate::oracle::*;
\ No newline at end of file
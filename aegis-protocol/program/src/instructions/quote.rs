@@ -0,0 +1 @@
+pub use crate::quote::*;
\ No newline at end of file
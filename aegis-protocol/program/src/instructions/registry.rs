@@ -0,0 +1 @@
+pub use crate::registry::*;
\ No newline at end of file
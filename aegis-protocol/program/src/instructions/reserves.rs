@@ -0,0 +1 @@
+pub use crate::reserves::*;
\ No newline at end of file
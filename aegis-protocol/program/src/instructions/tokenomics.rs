@@ -1,15 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
 use crate::{
     constants::{
-        AEGIS_DECIMALS, AEGIS_DISTRIBUTION_WEEKS, AEGIS_TOTAL_SUPPLY_LAMPORTS, AEGIS_WEEKLY_EMISSION,
+        AEGIS_DECIMALS, AEGIS_DISTRIBUTION_WEEKS, AEGIS_WEEKLY_EMISSION,
         BPS_DENOMINATOR, ECOSYSTEM_PERCENT_BPS, LM_PERCENT_BPS, MIN_LIQ_FOR_REWARDS, MIN_REWARD_POINTS,
         TEAM_PERCENT_BPS, WEEK_IN_SECONDS,
     },
     errors::ErrorCode,
-    seeds,
-    state::{EmissionVault, Pool, TeamVesting},
+    lp_staking, reentrancy, seeds,
+    state::{EmissionVault, Pool},
 };
 
 #[event]
@@ -28,6 +28,7 @@ pub struct PoolRewardsClaimed {
     pub boosted: bool,
     pub caller: Pubkey,
     pub points_redeemed: u128,
+    pub reward_per_share: u128,
 }
 
 #[derive(Accounts)]
@@ -73,6 +74,7 @@ pub struct InitializeEmissionVaultCore<'info> {
 pub struct DistributeWeeklyRewards<'info> {
     pub caller: Signer<'info>,
     #[account(
+        mut,
         seeds = [b"emission_vault"],
         bump = emission_vault.bump
     )]
@@ -94,16 +96,18 @@ pub struct DistributeWeeklyRewards<'info> {
     pub system_program: Program<'info, System>,
 }
 
+/// Harvests a pool's reward points into its LP reward vault for stakers to claim.
 #[derive(Accounts)]
 pub struct ClaimPoolRewards<'info> {
     pub caller: Signer<'info>,
     #[account(
         mut,
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
     #[account(
+        mut,
         seeds = [b"emission_vault"],
         bump = emission_vault.bump
     )]
@@ -117,13 +121,11 @@ pub struct ClaimPoolRewards<'info> {
     pub aegis_mint: Account<'info, Mint>,
     #[account(
         mut,
-        constraint = destination.mint == aegis_mint.key() @ ErrorCode::InvalidDestinationMint
+        seeds = [b"lp_reward_vault", pool.key().as_ref()],
+        bump,
+        constraint = reward_vault.mint == aegis_mint.key() @ ErrorCode::InvalidDestinationMint
     )]
-    pub destination: Account<'info, TokenAccount>,
-    #[account(mut, address = pool.vault_a)]
-    pub vault_a: Account<'info, TokenAccount>,
-    #[account(mut, address = pool.vault_b)]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub reward_vault: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
 }
 
@@ -133,7 +135,6 @@ pub fn initialize_emission_vault_core(ctx: Context<InitializeEmissionVaultCore>)
         ErrorCode::AlreadyInitialized
     );
 
-    let reward_minter_bump = ctx.bumps.reward_minter;
     let emission_bump = ctx.bumps.emission_vault;
 
     let emission_vault = &mut ctx.accounts.emission_vault;
@@ -147,7 +148,8 @@ pub fn initialize_emission_vault_core(ctx: Context<InitializeEmissionVaultCore>)
     emission_vault.lm_vault = Pubkey::default();
     emission_vault.team_vault = Pubkey::default();
     emission_vault.ecosystem_vault = Pubkey::default();
-    emission_vault._reserved = [0; 16];
+    emission_vault.locked = false;
+    emission_vault._reserved = [0; 15];
 
     Ok(())
 }
@@ -235,11 +237,9 @@ pub fn initialize_emission_token_accounts(ctx: Context<InitializeEmissionTokenAc
 
 #[inline(never)]
 pub fn distribute_weekly_rewards(ctx: Context<DistributeWeeklyRewards>) -> Result<()> {
-    // Reentrancy guard temporarily disabled to fix compilation
-    // let mut guard = ReentrancyGuard::new();
-    // guard.enter()?;
+    reentrancy::lock(&mut ctx.accounts.emission_vault, ctx.program_id)?;
 
-    let result = (|| {
+    (|| {
         let clock = Clock::get()?;
         let emission_vault = &ctx.accounts.emission_vault;
 
@@ -290,10 +290,9 @@ pub fn distribute_weekly_rewards(ctx: Context<DistributeWeeklyRewards>) -> Resul
             });
         }
 
+        reentrancy::unlock(&mut ctx.accounts.emission_vault);
         Ok(())
-    })();
-
-    result
+    })()
 }
 
 #[inline(never)]
@@ -392,12 +391,15 @@ fn transfer_rewards(
 
 pub fn claim_pool_rewards(ctx: Context<ClaimPoolRewards>) -> Result<()> {
     let clock = Clock::get()?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+    reentrancy::lock(&mut ctx.accounts.emission_vault, ctx.program_id)?;
     let pool = &mut ctx.accounts.pool;
 
     require!(pool.reward_points >= MIN_REWARD_POINTS, ErrorCode::NoRewardPoints);
+    require!(pool.staked_lp > 0, ErrorCode::NoStakedLiquidity);
 
-    let liquid_a = ctx.accounts.vault_a.amount;
-    let liquid_b = ctx.accounts.vault_b.amount;
+    let liquid_a = pool.reserve_a;
+    let liquid_b = pool.reserve_b;
     let has_liquidity = liquid_a
         .checked_add(liquid_b)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?
@@ -430,7 +432,7 @@ pub fn claim_pool_rewards(ctx: Context<ClaimPoolRewards>) -> Result<()> {
             ctx.accounts.token_program.to_account_info(),
             Transfer {
                 from: ctx.accounts.lm_vault.to_account_info(),
-                to: ctx.accounts.destination.to_account_info(),
+                to: ctx.accounts.reward_vault.to_account_info(),
                 authority: ctx.accounts.emission_vault.to_account_info(),
             },
             &[emission_signer_slice],
@@ -438,8 +440,11 @@ pub fn claim_pool_rewards(ctx: Context<ClaimPoolRewards>) -> Result<()> {
         transfer_amount,
     )?;
 
+    pool.reward_per_share = lp_staking::accrue_reward_per_share(pool.reward_per_share, transfer_amount, pool.staked_lp)?;
     pool.reward_points = 0;
     pool.last_reward_claim_ts = clock.unix_timestamp;
+    reentrancy::unlock(pool);
+    reentrancy::unlock(&mut ctx.accounts.emission_vault);
 
     emit!(PoolRewardsClaimed {
         pool: pool.key(),
@@ -447,6 +452,7 @@ pub fn claim_pool_rewards(ctx: Context<ClaimPoolRewards>) -> Result<()> {
         boosted,
         caller: ctx.accounts.caller.key(),
         points_redeemed: reward_amount_u128,
+        reward_per_share: pool.reward_per_share,
     });
 
     Ok(())
@@ -0,0 +1 @@
+pub use crate::twap::*;
\ No newline at end of file
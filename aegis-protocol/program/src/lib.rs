@@ -1,11 +1,27 @@
+pub mod circuit_breaker;
+pub mod clmm;
 pub mod constants;
+pub mod curve;
+pub mod dca;
 pub mod errors;
+pub mod fee_tiers;
+pub mod flash_loan;
 pub mod instructions;
+pub mod limit_order;
+pub mod lp_staking;
 pub mod seeds;
 pub mod pool;
+pub mod quote;
+pub mod reentrancy;
+pub mod registry;
+pub mod reserves;
 pub mod math;
+pub mod oracle;
 pub mod validation;
 pub mod state;
+pub mod tick_math;
+pub mod token_utils;
+pub mod twap;
 
 pub use errors::ErrorCode;
 pub use state::*;
@@ -18,26 +34,286 @@ declare_id!("AerttabNDRDQkaHZBKka1JFGytct6Bx5hV5Jonrvwryu");
 pub mod aegis_protocol {
     use super::*;
 
-    pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
-        instructions::pool::initialize_pool(ctx, fee_bps)
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        fee_bps: u16,
+        curve_type: CurveType,
+        amp: u64,
+        weight_a_bps: u16,
+        symbol_a: String,
+        symbol_b: String,
+    ) -> Result<()> {
+        instructions::pool::initialize_pool(ctx, fee_bps, curve_type, amp, weight_a_bps, symbol_a, symbol_b)
     }
 
-    pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: u64, amount_b: u64) -> Result<()> {
-        instructions::pool::add_liquidity(ctx, amount_a, amount_b)
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::pool::migrate_pool(ctx)
     }
 
-    pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, lp_amount: u64) -> Result<()> {
-        instructions::pool::remove_liquidity(ctx, lp_amount)
+    pub fn add_liquidity(
+        ctx: Context<AddLiquidity>,
+        amount_a: u64,
+        amount_b: u64,
+        min_lp_out: u64,
+        max_ratio_deviation_bps: Option<u16>,
+    ) -> Result<()> {
+        instructions::pool::add_liquidity(ctx, amount_a, amount_b, min_lp_out, max_ratio_deviation_bps)
     }
 
-    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64, a_to_b: bool) -> Result<()> {
-        instructions::pool::swap(ctx, amount_in, min_amount_out, a_to_b)
+    pub fn add_liquidity_single(
+        ctx: Context<AddLiquiditySingle>,
+        amount_in: u64,
+        is_a: bool,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        instructions::pool::add_liquidity_single(ctx, amount_in, is_a, min_lp_out)
+    }
+
+    pub fn remove_liquidity(
+        ctx: Context<RemoveLiquidity>,
+        lp_amount: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        instructions::pool::remove_liquidity(ctx, lp_amount, min_amount_a, min_amount_b)
+    }
+
+    pub fn swap(
+        ctx: Context<Swap>,
+        amount_in: u64,
+        min_amount_out: u64,
+        a_to_b: bool,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::pool::swap(ctx, amount_in, min_amount_out, a_to_b, deadline)
+    }
+
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        amount_out: u64,
+        max_amount_in: u64,
+        a_to_b: bool,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::pool::swap_exact_out(ctx, amount_out, max_amount_in, a_to_b, deadline)
+    }
+
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::pool::route_swap(ctx, amount_in, min_amount_out, deadline)
+    }
+
+    pub fn flash_loan(ctx: Context<FlashLoan>, amount_a: u64, amount_b: u64) -> Result<()> {
+        instructions::flash_loan::flash_loan(ctx, amount_a, amount_b)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        instructions::flash_loan::flash_repay(ctx)
+    }
+
+    pub fn configure_oracle(
+        ctx: Context<ConfigureOracle>,
+        oracle_type: OracleType,
+        feed_a: Pubkey,
+        feed_b: Pubkey,
+        max_staleness_seconds: i64,
+    ) -> Result<()> {
+        instructions::oracle::configure_oracle(ctx, oracle_type, feed_a, feed_b, max_staleness_seconds)
+    }
+
+    pub fn update_oracle_manual(
+        ctx: Context<UpdateOracleManual>,
+        price_a: i64,
+        expo_a: i32,
+        price_b: i64,
+        expo_b: i32,
+    ) -> Result<()> {
+        instructions::oracle::update_oracle_manual(ctx, price_a, expo_a, price_b, expo_b)
+    }
+
+    pub fn update_oracle_from_pyth(ctx: Context<UpdateOracleFromPyth>) -> Result<()> {
+        instructions::oracle::update_oracle_from_pyth(ctx)
+    }
+
+    pub fn configure_oracle_guard(
+        ctx: Context<ConfigureOracleGuard>,
+        enabled: bool,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        instructions::oracle::configure_oracle_guard(ctx, enabled, tolerance_bps)
+    }
+
+    pub fn configure_circuit_breaker(
+        ctx: Context<ConfigureCircuitBreaker>,
+        max_price_impact_bps: u16,
+        max_window_move_bps: u16,
+    ) -> Result<()> {
+        instructions::circuit_breaker::configure_circuit_breaker(ctx, max_price_impact_bps, max_window_move_bps)
+    }
+
+    pub fn resume_pool(ctx: Context<ConfigureCircuitBreaker>) -> Result<()> {
+        instructions::circuit_breaker::resume_pool(ctx)
+    }
+
+    pub fn initialize_observations(ctx: Context<InitializeObservations>) -> Result<()> {
+        instructions::twap::initialize_observations(ctx)
+    }
+
+    pub fn record_observation(ctx: Context<RecordObservation>) -> Result<()> {
+        instructions::twap::record_observation(ctx)
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        fee_bps: u16,
+        tick_spacing: u16,
+        initial_sqrt_price_x64: u128,
+    ) -> Result<()> {
+        instructions::clmm::initialize_cl_pool(ctx, fee_bps, tick_spacing, initial_sqrt_price_x64)
+    }
+
+    pub fn initialize_tick_array(ctx: Context<InitializeTickArray>, start_tick_index: i32) -> Result<()> {
+        instructions::clmm::initialize_tick_array(ctx, start_tick_index)
+    }
+
+    pub fn open_cl_position(ctx: Context<OpenClPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        instructions::clmm::open_cl_position(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn increase_cl_liquidity(
+        ctx: Context<IncreaseClLiquidity>,
+        liquidity_delta: u128,
+        max_amount_a: u64,
+        max_amount_b: u64,
+    ) -> Result<()> {
+        instructions::clmm::increase_cl_liquidity(ctx, liquidity_delta, max_amount_a, max_amount_b)
+    }
+
+    pub fn decrease_cl_liquidity(
+        ctx: Context<DecreaseClLiquidity>,
+        liquidity_delta: u128,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        instructions::clmm::decrease_cl_liquidity(ctx, liquidity_delta, min_amount_a, min_amount_b)
+    }
+
+    pub fn collect_cl_fees(ctx: Context<CollectClFees>) -> Result<()> {
+        instructions::clmm::collect_cl_fees(ctx)
+    }
+
+    pub fn cl_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        a_to_b: bool,
+        sqrt_price_limit_x64: u128,
+        deadline: i64,
+    ) -> Result<()> {
+        instructions::clmm::cl_swap(ctx, amount_in, min_amount_out, a_to_b, sqrt_price_limit_x64, deadline)
+    }
+
+    pub fn set_cl_emergency_mode(ctx: Context<SetClEmergencyMode>, emergency_mode: bool) -> Result<()> {
+        instructions::clmm::set_cl_emergency_mode(ctx, emergency_mode)
+    }
+
+    pub fn initialize_fee_tiers(ctx: Context<InitializeFeeTiers>) -> Result<()> {
+        instructions::fee_tiers::initialize_fee_tiers(ctx)
+    }
+
+    pub fn add_fee_tier(ctx: Context<UpdateFeeTiers>, fee_bps: u16) -> Result<()> {
+        instructions::fee_tiers::add_fee_tier(ctx, fee_bps)
+    }
+
+    pub fn remove_fee_tier(ctx: Context<UpdateFeeTiers>, fee_bps: u16) -> Result<()> {
+        instructions::fee_tiers::remove_fee_tier(ctx, fee_bps)
+    }
+
+    pub fn set_fee_tier_admin(ctx: Context<UpdateFeeTiers>, new_admin: Pubkey) -> Result<()> {
+        instructions::fee_tiers::set_fee_tier_admin(ctx, new_admin)
+    }
+
+    pub fn initialize_pool_registry(ctx: Context<InitializePoolRegistry>) -> Result<()> {
+        instructions::registry::initialize_pool_registry(ctx)
+    }
+
+    pub fn add_registry_page(ctx: Context<AddRegistryPage>) -> Result<()> {
+        instructions::registry::add_registry_page(ctx)
     }
 
     pub fn health_check(ctx: Context<HealthCheck>) -> Result<()> {
         instructions::pool::health_check(ctx)
     }
 
+    pub fn initialize_order_escrow(ctx: Context<InitializeOrderEscrow>) -> Result<()> {
+        instructions::limit_order::initialize_order_escrow(ctx)
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        order_id: u64,
+        amount_in: u64,
+        min_amount_out: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        instructions::limit_order::place_limit_order(ctx, order_id, amount_in, min_amount_out, expires_at)
+    }
+
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        instructions::limit_order::cancel_limit_order(ctx)
+    }
+
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        instructions::limit_order::fill_limit_order(ctx)
+    }
+
+    pub fn open_dca_order(
+        ctx: Context<OpenDcaOrder>,
+        order_id: u64,
+        total_amount: u64,
+        slice_amount: u64,
+        interval_seconds: i64,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        instructions::dca::open_dca_order(ctx, order_id, total_amount, slice_amount, interval_seconds, max_slippage_bps)
+    }
+
+    pub fn close_dca_order(ctx: Context<CloseDcaOrder>) -> Result<()> {
+        instructions::dca::close_dca_order(ctx)
+    }
+
+    pub fn execute_dca_slice(ctx: Context<ExecuteDcaSlice>) -> Result<()> {
+        instructions::dca::execute_dca_slice(ctx)
+    }
+
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        instructions::reserves::skim(ctx)
+    }
+
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        instructions::reserves::sync(ctx)
+    }
+
+    pub fn quote_swap(ctx: Context<Quote>, amount_in: u64, a_to_b: bool) -> Result<()> {
+        instructions::quote::quote_swap(ctx, amount_in, a_to_b)
+    }
+
+    pub fn quote_swap_exact_out(ctx: Context<Quote>, amount_out: u64, a_to_b: bool) -> Result<()> {
+        instructions::quote::quote_swap_exact_out(ctx, amount_out, a_to_b)
+    }
+
+    pub fn quote_add_liquidity(ctx: Context<Quote>, amount_a: u64, amount_b: u64) -> Result<()> {
+        instructions::quote::quote_add_liquidity(ctx, amount_a, amount_b)
+    }
+
+    pub fn quote_remove_liquidity(ctx: Context<Quote>, lp_amount: u64) -> Result<()> {
+        instructions::quote::quote_remove_liquidity(ctx, lp_amount)
+    }
+
     pub fn initialize_emission_vault_core(ctx: Context<InitializeEmissionVaultCore>) -> Result<()> {
         instructions::tokenomics::initialize_emission_vault_core(ctx)
     }
@@ -50,4 +326,24 @@ pub mod aegis_protocol {
     pub fn claim_pool_rewards(ctx: Context<ClaimPoolRewards>) -> Result<()> {
         instructions::tokenomics::claim_pool_rewards(ctx)
     }
+
+    pub fn initialize_lp_staking(ctx: Context<InitializeLpStaking>) -> Result<()> {
+        instructions::lp_staking::initialize_lp_staking(ctx)
+    }
+
+    pub fn open_lp_position(ctx: Context<OpenLpPosition>) -> Result<()> {
+        instructions::lp_staking::open_lp_position(ctx)
+    }
+
+    pub fn stake_lp(ctx: Context<MoveLpStake>, amount: u64) -> Result<()> {
+        instructions::lp_staking::stake_lp(ctx, amount)
+    }
+
+    pub fn unstake_lp(ctx: Context<MoveLpStake>, amount: u64) -> Result<()> {
+        instructions::lp_staking::unstake_lp(ctx, amount)
+    }
+
+    pub fn claim_lp_rewards(ctx: Context<ClaimLpRewards>) -> Result<()> {
+        instructions::lp_staking::claim_lp_rewards(ctx)
+    }
 }
\ No newline at end of file
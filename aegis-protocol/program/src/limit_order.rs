@@ -0,0 +1,341 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    constants::{BPS_DENOMINATOR, LIMIT_ORDER_CRANK_FEE_BPS},
+    errors::ErrorCode,
+    pool::EscrowSwap,
+    reentrancy, seeds,
+    state::{LimitOrder, OracleConfig, Pool, PoolObservations},
+    token_utils, validation,
+};
+
+#[event]
+pub struct LimitOrderPlaced {
+    pub order: Pubkey,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct LimitOrderFilled {
+    pub order: Pubkey,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub cranker: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub cranker_fee: u64,
+}
+
+#[event]
+pub struct LimitOrderCancelled {
+    pub order: Pubkey,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount_refunded: u64,
+}
+
+/// One escrow per pool and mint holds the input of every open order selling that mint.
+#[derive(Accounts)]
+pub struct InitializeOrderEscrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mint::token_program = token_program,
+        constraint = mint.key() == pool.mint_a || mint.key() == pool.mint_b @ ErrorCode::InvalidVault
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = pool,
+        token::token_program = token_program,
+        seeds = [b"order_escrow", pool.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = LimitOrder::SIZE,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), order_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    #[account(
+        mint::token_program = token_program,
+        constraint = input_mint.key() == pool.mint_a || input_mint.key() == pool.mint_b @ ErrorCode::InvalidVault
+    )]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), input_mint.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = input_mint)]
+    pub owner_source: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = pool,
+        has_one = owner,
+        has_one = input_mint,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    #[account(mint::token_program = token_program)]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), input_mint.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = input_mint)]
+    pub owner_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    pub cranker: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = pool,
+        has_one = owner,
+        seeds = [b"limit_order", pool.key().as_ref(), owner.key().as_ref(), order.order_id.to_le_bytes().as_ref()],
+        bump = order.bump
+    )]
+    pub order: Account<'info, LimitOrder>,
+    /// CHECK: order owner, receives the order account's rent; matched by `has_one`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"order_escrow", pool.key().as_ref(), order.input_mint.as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, constraint = owner_destination.owner == owner.key() @ ErrorCode::InvalidRecipient)]
+    pub owner_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub cranker_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle_config.bump,
+        has_one = pool
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+pub fn initialize_order_escrow(_ctx: Context<InitializeOrderEscrow>) -> Result<()> {
+    Ok(())
+}
+
+/// Escrows `amount_in` of `input_mint`. The order fills once the pool pays at least
+/// `min_amount_out` for it, i.e. once the price reaches `min_amount_out / amount_in`.
+pub fn place_limit_order(
+    ctx: Context<PlaceLimitOrder>,
+    order_id: u64,
+    amount_in: u64,
+    min_amount_out: u64,
+    expires_at: i64,
+) -> Result<()> {
+    validation::assert_positive(amount_in)?;
+    validation::assert_positive(min_amount_out)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(expires_at > now, ErrorCode::OrderExpired);
+
+    let accounts = ctx.accounts;
+    let balance_before = accounts.escrow.amount;
+    token_utils::transfer_checked(
+        accounts.token_program.to_account_info(),
+        accounts.owner_source.to_account_info(),
+        &accounts.input_mint,
+        accounts.escrow.to_account_info(),
+        accounts.owner.to_account_info(),
+        &[],
+        amount_in,
+    )?;
+    let received = token_utils::received_amount(&mut accounts.escrow, balance_before)?;
+
+    let order = &mut accounts.order;
+    order.pool = accounts.pool.key();
+    order.owner = accounts.owner.key();
+    order.order_id = order_id;
+    order.input_mint = accounts.input_mint.key();
+    order.a_to_b = order.input_mint == accounts.pool.mint_a;
+    order.amount_in = received;
+    order.min_amount_out = min_amount_out;
+    order.created_at = now;
+    order.expires_at = expires_at;
+    order.bump = ctx.bumps.order;
+
+    emit!(LimitOrderPlaced {
+        order: order.key(),
+        pool: order.pool,
+        owner: order.owner,
+        a_to_b: order.a_to_b,
+        amount_in: received,
+        min_amount_out,
+        expires_at,
+    });
+    Ok(())
+}
+
+/// Returns the escrowed input to the owner and closes the order.
+pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+    let accounts = ctx.accounts;
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token_utils::transfer_checked(
+        accounts.token_program.to_account_info(),
+        accounts.escrow.to_account_info(),
+        &accounts.input_mint,
+        accounts.owner_destination.to_account_info(),
+        accounts.pool.to_account_info(),
+        &[&signer_seeds_slice],
+        accounts.order.amount_in,
+    )?;
+    reentrancy::unlock(&mut accounts.pool);
+
+    emit!(LimitOrderCancelled {
+        order: accounts.order.key(),
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        amount_refunded: accounts.order.amount_in,
+    });
+    Ok(())
+}
+
+/// Permissionless crank: swaps the escrowed input through the pool once it yields the order's
+/// minimum after the cranker's `LIMIT_ORDER_CRANK_FEE_BPS` cut, which is paid in the output token.
+pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= ctx.accounts.order.expires_at, ErrorCode::OrderExpired);
+    validation::assert_no_flash_loan(&ctx.accounts.pool)?;
+
+    let accounts = ctx.accounts;
+    let a_to_b = accounts.order.a_to_b;
+    let output_mint = if a_to_b { accounts.pool.mint_b } else { accounts.pool.mint_a };
+    require_keys_eq!(accounts.owner_destination.mint, output_mint, ErrorCode::InvalidDestinationMint);
+    require_keys_eq!(accounts.cranker_destination.mint, output_mint, ErrorCode::InvalidDestinationMint);
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+
+    let mut escrow_swap = EscrowSwap {
+        pool: &mut accounts.pool,
+        escrow: &accounts.escrow,
+        vault_a: &mut accounts.vault_a,
+        vault_b: &mut accounts.vault_b,
+        mint_a: &accounts.mint_a,
+        mint_b: &accounts.mint_b,
+        token_program_a: &accounts.token_program_a,
+        token_program_b: &accounts.token_program_b,
+        oracle_config: accounts.oracle_config.as_deref(),
+        observations: accounts.observations.as_deref_mut().map(|observations| &mut **observations),
+    };
+    let (received_in, amount_out) =
+        escrow_swap.execute(accounts.owner.key(), a_to_b, accounts.order.amount_in, now)?;
+    let (owner_out, cranker_fee) = split_fill_output(amount_out);
+    // Measure what the owner received so an output transfer fee cannot dip below the minimum
+    let owner_received = escrow_swap.pay_out(a_to_b, &mut accounts.owner_destination, owner_out)?;
+    require!(owner_received >= accounts.order.min_amount_out, ErrorCode::OrderNotFillable);
+    escrow_swap.pay_out(a_to_b, &mut accounts.cranker_destination, cranker_fee)?;
+    reentrancy::unlock(&mut accounts.pool);
+
+    emit!(LimitOrderFilled {
+        order: accounts.order.key(),
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        cranker: accounts.cranker.key(),
+        amount_in: received_in,
+        amount_out: owner_received,
+        cranker_fee,
+    });
+    Ok(())
+}
+
+/// Splits a fill's pool output into the owner's share and the cranker's fee.
+fn split_fill_output(amount_out: u64) -> (u64, u64) {
+    let cranker_fee = (amount_out as u128 * LIMIT_ORDER_CRANK_FEE_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+    (amount_out - cranker_fee, cranker_fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_output_pays_cranker() {
+        assert_eq!(split_fill_output(100_000), (99_900, 100));
+        // Small fills round the cranker's cut down to nothing
+        assert_eq!(split_fill_output(999), (999, 0));
+    }
+}
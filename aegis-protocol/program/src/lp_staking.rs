@@ -0,0 +1,340 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+use crate::{
+    errors::ErrorCode,
+    math::{self, U256},
+    reentrancy, seeds,
+    state::{EmissionVault, LpPosition, Pool},
+    validation,
+};
+
+#[event]
+pub struct LpStaked {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked: u64,
+    pub pool_staked: u64,
+}
+
+#[event]
+pub struct LpUnstaked {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked: u64,
+    pub pool_staked: u64,
+}
+
+#[event]
+pub struct LpRewardsClaimed {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Creates the pool-owned accounts holding staked LP and the AEGIS rewards harvested for stakers.
+#[derive(Accounts)]
+pub struct InitializeLpStaking<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+    #[account(seeds = [b"emission_vault"], bump = emission_vault.bump)]
+    pub emission_vault: Account<'info, EmissionVault>,
+    #[account(address = emission_vault.reward_mint)]
+    pub aegis_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = lp_mint,
+        token::authority = pool,
+        seeds = [b"lp_stake_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub lp_stake_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = payer,
+        token::mint = aegis_mint,
+        token::authority = pool,
+        seeds = [b"lp_reward_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLpPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = LpPosition::SIZE,
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LpPosition>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by `stake_lp` and `unstake_lp`; `owner_lp` is the source or destination of the LP tokens.
+#[derive(Accounts)]
+pub struct MoveLpStake<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LpPosition>,
+    #[account(mut, seeds = [b"lp_stake_vault", pool.key().as_ref()], bump)]
+    pub lp_stake_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = owner_lp.mint == pool.lp_mint @ ErrorCode::InvalidLpMint)]
+    pub owner_lp: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLpRewards<'info> {
+    pub owner: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        has_one = pool,
+        has_one = owner,
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LpPosition>,
+    #[account(mut, seeds = [b"lp_reward_vault", pool.key().as_ref()], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = destination.mint == reward_vault.mint @ ErrorCode::InvalidDestinationMint
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn initialize_lp_staking(_ctx: Context<InitializeLpStaking>) -> Result<()> {
+    Ok(())
+}
+
+pub fn open_lp_position(ctx: Context<OpenLpPosition>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.pool = ctx.accounts.pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.staked = 0;
+    position.reward_debt = 0;
+    position.pending_rewards = 0;
+    position.bump = ctx.bumps.position;
+    Ok(())
+}
+
+pub fn stake_lp(ctx: Context<MoveLpStake>, amount: u64) -> Result<()> {
+    validation::assert_positive(amount)?;
+    let accounts = ctx.accounts;
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+
+    token::transfer(
+        CpiContext::new(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.owner_lp.to_account_info(),
+                to: accounts.lp_stake_vault.to_account_info(),
+                authority: accounts.owner.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let reward_per_share = accounts.pool.reward_per_share;
+    let position = &mut accounts.position;
+    settle_position(position, reward_per_share)?;
+    position.staked = math::add_u64(position.staked, amount)?;
+    position.reward_debt = accumulated_rewards(position.staked, reward_per_share)?;
+    accounts.pool.staked_lp = math::add_u64(accounts.pool.staked_lp, amount)?;
+    reentrancy::unlock(&mut accounts.pool);
+
+    emit!(LpStaked {
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        amount,
+        staked: accounts.position.staked,
+        pool_staked: accounts.pool.staked_lp,
+    });
+    Ok(())
+}
+
+/// Returns staked LP to the owner. Rewards earned so far stay claimable on the position.
+pub fn unstake_lp(ctx: Context<MoveLpStake>, amount: u64) -> Result<()> {
+    validation::assert_positive(amount)?;
+    let accounts = ctx.accounts;
+    require!(accounts.position.staked >= amount, ErrorCode::InsufficientBalance);
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+
+    let reward_per_share = accounts.pool.reward_per_share;
+    let position = &mut accounts.position;
+    settle_position(position, reward_per_share)?;
+    position.staked -= amount;
+    position.reward_debt = accumulated_rewards(position.staked, reward_per_share)?;
+    accounts.pool.staked_lp = math::sub_u64(accounts.pool.staked_lp, amount)?;
+
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.lp_stake_vault.to_account_info(),
+                to: accounts.owner_lp.to_account_info(),
+                authority: accounts.pool.to_account_info(),
+            },
+            &[&signer_seeds_slice],
+        ),
+        amount,
+    )?;
+    reentrancy::unlock(&mut accounts.pool);
+
+    emit!(LpUnstaked {
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        amount,
+        staked: accounts.position.staked,
+        pool_staked: accounts.pool.staked_lp,
+    });
+    Ok(())
+}
+
+/// Pays the position's share of every harvest since it last settled.
+pub fn claim_lp_rewards(ctx: Context<ClaimLpRewards>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let reward_per_share = accounts.pool.reward_per_share;
+    let position = &mut accounts.position;
+    settle_position(position, reward_per_share)?;
+    let amount = position.pending_rewards;
+    require!(amount > 0, ErrorCode::NoRewardPoints);
+    position.pending_rewards = 0;
+
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token::transfer(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            Transfer {
+                from: accounts.reward_vault.to_account_info(),
+                to: accounts.destination.to_account_info(),
+                authority: accounts.pool.to_account_info(),
+            },
+            &[&signer_seeds_slice],
+        ),
+        amount,
+    )?;
+    reentrancy::unlock(&mut accounts.pool);
+
+    emit!(LpRewardsClaimed {
+        pool: accounts.pool.key(),
+        owner: accounts.owner.key(),
+        amount,
+    });
+    Ok(())
+}
+
+/// Adds `reward` spread over `staked` LP tokens to the Q64.64 per-share accumulator.
+pub(crate) fn accrue_reward_per_share(reward_per_share: u128, reward: u64, staked: u64) -> Result<u128> {
+    require!(staked > 0, ErrorCode::NoStakedLiquidity);
+    let increment = ((reward as u128) << 64) / staked as u128;
+    reward_per_share
+        .checked_add(increment)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Rewards a stake of `staked` has earned from the accumulator's origin, rounded down.
+pub(crate) fn accumulated_rewards(staked: u64, reward_per_share: u128) -> Result<u128> {
+    let scaled = (U256::from(staked) * U256::from(reward_per_share)) >> 64;
+    require!(scaled <= U256::from(u128::MAX), ErrorCode::ArithmeticOverflow);
+    Ok(scaled.as_u128())
+}
+
+/// Moves rewards earned since the last settlement into `pending_rewards`.
+fn settle_position(position: &mut LpPosition, reward_per_share: u128) -> Result<()> {
+    let accumulated = accumulated_rewards(position.staked, reward_per_share)?;
+    let earned: u64 = accumulated
+        .saturating_sub(position.reward_debt)
+        .try_into()
+        .map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
+    position.pending_rewards = math::add_u64(position.pending_rewards, earned)?;
+    position.reward_debt = accumulated;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(staked: u64, reward_per_share: u128) -> LpPosition {
+        LpPosition {
+            pool: Pubkey::default(),
+            owner: Pubkey::default(),
+            staked,
+            reward_debt: accumulated_rewards(staked, reward_per_share).unwrap(),
+            pending_rewards: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn harvests_split_pro_rata_between_stakers() {
+        let mut acc = 0u128;
+        let mut early = position(300, acc);
+        acc = accrue_reward_per_share(acc, 1_000, 300).unwrap();
+
+        // A late staker only shares in harvests after it joins.
+        let mut late = position(100, acc);
+        acc = accrue_reward_per_share(acc, 1_000, 400).unwrap();
+
+        settle_position(&mut early, acc).unwrap();
+        settle_position(&mut late, acc).unwrap();
+        assert_eq!(early.pending_rewards, 1_749);
+        assert_eq!(late.pending_rewards, 250);
+        assert!(early.pending_rewards + late.pending_rewards <= 2_000);
+
+        // Settling twice at the same accumulator pays nothing more.
+        settle_position(&mut early, acc).unwrap();
+        assert_eq!(early.pending_rewards, 1_749);
+    }
+
+    #[test]
+    fn accrual_requires_stakers() {
+        assert!(accrue_reward_per_share(0, 1_000, 0).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::errors::ErrorCode;
+use crate::{constants::BPS_DENOMINATOR, errors::ErrorCode};
 
 pub fn add_u64(a: u64, b: u64) -> Result<u64, ErrorCode> {
     a.checked_add(b).ok_or(ErrorCode::ArithmeticOverflow)
@@ -44,4 +44,171 @@ pub fn pow10(exponent: u32) -> Result<u64, ErrorCode> {
         9 => Ok(1000000000),
         _ => Err(ErrorCode::ArithmeticOverflow),
     }
-}
\ No newline at end of file
+}
+
+/// Fixed-point scale used by `ln_wad`, `exp_wad` and `pow_wad` (18 decimals)
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+/// ln(2) scaled by `WAD`
+const LN2_WAD: i128 = 693_147_180_559_945_309;
+
+/// Largest argument accepted by `exp_wad` before the result stops fitting in a WAD u128
+const MAX_EXP_WAD: i128 = 47 * WAD as i128;
+
+/// Below this `exp_wad` rounds to zero
+const MIN_EXP_WAD: i128 = -42 * WAD as i128;
+
+/// Natural logarithm of a WAD-scaled value, WAD-scaled.
+pub fn ln_wad(x: u128) -> Result<i128, ErrorCode> {
+    if x == 0 {
+        return Err(ErrorCode::ArithmeticOverflow);
+    }
+
+    // Normalise x = m * 2^k with m in [1, 2)
+    let mut k: i128 = 0;
+    let mut m = x;
+    while m >= 2 * WAD {
+        m >>= 1;
+        k += 1;
+    }
+    while m < WAD {
+        m <<= 1;
+        k -= 1;
+    }
+
+    // ln(m) = 2 * atanh(z) with z = (m - 1) / (m + 1) <= 1/3
+    let z = (m - WAD) * WAD / (m + WAD);
+    let z_squared = z * z / WAD;
+    let mut term = z;
+    let mut sum: u128 = 0;
+    let mut denominator: u128 = 1;
+    while term > 0 {
+        sum += term / denominator;
+        term = term * z_squared / WAD;
+        denominator += 2;
+    }
+
+    k.checked_mul(LN2_WAD)
+        .and_then(|v| v.checked_add(2 * sum as i128))
+        .ok_or(ErrorCode::ArithmeticOverflow)
+}
+
+/// e^y for a WAD-scaled exponent, WAD-scaled.
+pub fn exp_wad(y: i128) -> Result<u128, ErrorCode> {
+    if y < MIN_EXP_WAD {
+        return Ok(0);
+    }
+    if y > MAX_EXP_WAD {
+        return Err(ErrorCode::ArithmeticOverflow);
+    }
+
+    // y = k * ln(2) + r with r in [0, ln 2)
+    let k = y.div_euclid(LN2_WAD);
+    let r = y.rem_euclid(LN2_WAD) as u128;
+
+    let mut term = WAD;
+    let mut sum = WAD;
+    let mut n: u128 = 1;
+    while term > 0 {
+        term = term * r / (WAD * n);
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        sum.checked_mul(1u128 << k).ok_or(ErrorCode::ArithmeticOverflow)
+    } else {
+        Ok(sum >> (-k))
+    }
+}
+
+/// base^exponent for WAD-scaled operands. Accurate to roughly 1e-15 relative error.
+pub fn pow_wad(base: u128, exponent: u128) -> Result<u128, ErrorCode> {
+    if exponent == 0 {
+        return Ok(WAD);
+    }
+    if base == 0 {
+        return Ok(0);
+    }
+    let exponent: i128 = exponent.try_into().map_err(|_| ErrorCode::ArithmeticOverflow)?;
+    let product = ln_wad(base)?
+        .checked_mul(exponent)
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / WAD as i128;
+    exp_wad(product)
+}
+
+/// a^w * b^(1 - w) with `weight_a_bps` = w in basis points, rounded down.
+pub fn weighted_geometric_mean(a: u64, b: u64, weight_a_bps: u16) -> Result<u128, ErrorCode> {
+    if a == 0 || b == 0 {
+        return Ok(0);
+    }
+    let weight_a = weight_a_bps as i128;
+    let weight_b = BPS_DENOMINATOR as i128 - weight_a;
+    let ln_a = ln_wad(a as u128 * WAD)?;
+    let ln_b = ln_wad(b as u128 * WAD)?;
+    let weighted = ln_a
+        .checked_mul(weight_a)
+        .and_then(|v| v.checked_add(ln_b.checked_mul(weight_b)?))
+        .ok_or(ErrorCode::ArithmeticOverflow)?
+        / BPS_DENOMINATOR as i128;
+    Ok(exp_wad(weighted)? / WAD)
+}
+
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        /// 256-bit unsigned integer for invariant math that overflows u128
+        pub struct U256(4);
+    }
+}
+
+pub use u256::U256;
+
+pub fn u256_div_ceil(numerator: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: u128, expected: u128, tolerance: u128) {
+        let diff = actual.abs_diff(expected);
+        assert!(diff <= tolerance, "actual={} expected={} diff={}", actual, expected, diff);
+    }
+
+    #[test]
+    fn ln_and_exp_round_trip() {
+        for x in [1u128, WAD / 3, WAD, 2 * WAD, 7 * WAD / 5, 1_000_000 * WAD] {
+            let back = exp_wad(ln_wad(x).unwrap()).unwrap();
+            assert_close(back, x, x / 1_000_000_000_000_000 + 2);
+        }
+        assert_eq!(ln_wad(WAD).unwrap(), 0);
+        assert_close(ln_wad(2 * WAD).unwrap() as u128, LN2_WAD as u128, 2);
+    }
+
+    #[test]
+    fn pow_matches_known_values() {
+        assert_close(pow_wad(4 * WAD, WAD / 2).unwrap(), 2 * WAD, 1_000);
+        assert_close(pow_wad(WAD / 2, 2 * WAD).unwrap(), WAD / 4, 1_000);
+        assert_close(pow_wad(9 * WAD / 10, 4 * WAD).unwrap(), 656_100_000_000_000_000, 1_000);
+        assert_eq!(pow_wad(0, WAD).unwrap(), 0);
+        assert_eq!(pow_wad(3 * WAD, 0).unwrap(), WAD);
+    }
+
+    #[test]
+    fn weighted_mean_reduces_to_sqrt_for_even_weights() {
+        let (a, b) = (1_000_000_000u64, 4_000_000_000u64);
+        let mean = weighted_geometric_mean(a, b, 5_000).unwrap();
+        assert_close(mean, integer_sqrt(a as u128 * b as u128), 1);
+        // 80/20: 1e9^0.8 * 1e8^0.2 = 10^8.8
+        let skewed = weighted_geometric_mean(1_000_000_000, 100_000_000, 8_000).unwrap();
+        assert_close(skewed, 630_957_344, 2);
+    }
+}
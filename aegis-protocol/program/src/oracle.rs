@@ -1,12 +1,14 @@
 use anchor_lang::prelude::*;
-use pyth_sdk_solana::load_price_feed_from_account_info;
+use pyth_sdk_solana::state::SolanaPriceAccount;
 use std::str::FromStr;
 
 use crate::{
-    constants::{DEFAULT_MAX_STALENESS_SECONDS, ORACLE_SLIPPAGE_BPS, PYTH_PROGRAM_ID},
+    constants::{
+        BPS_DENOMINATOR, DEFAULT_MAX_STALENESS_SECONDS, FEATURE_ORACLE_GUARD, ORACLE_SLIPPAGE_BPS,
+        PYTH_PROGRAM_ID,
+    },
     errors::ErrorCode,
     math,
-    seeds,
     state::{OracleConfig, OracleType, Pool},
     validation,
 };
@@ -16,7 +18,7 @@ pub struct ConfigureOracle<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
@@ -37,13 +39,29 @@ pub struct UpdateOracleManual<'info> {
     pub oracle_config: Account<'info, OracleConfig>,
     pub authority: Signer<'info>,
     #[account(
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
     pub clock: Sysvar<'info, Clock>,
 }
 
+#[derive(Accounts)]
+pub struct ConfigureOracleGuard<'info> {
+    pub creator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump,
+        has_one = creator @ ErrorCode::Unauthorized
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(address = pool.mint_a)]
+    pub mint_a: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(address = pool.mint_b)]
+    pub mint_b: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+}
+
 pub fn configure_oracle(
     ctx: Context<ConfigureOracle>,
     oracle_type: OracleType,
@@ -82,7 +100,7 @@ pub struct UpdateOracleFromPyth<'info> {
     #[account(mut, has_one = pool)]
     pub oracle_config: Account<'info, OracleConfig>,
     #[account(
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
@@ -137,12 +155,36 @@ pub fn update_oracle_from_pyth(ctx: Context<UpdateOracleFromPyth>) -> Result<()>
     Ok(())
 }
 
+pub fn configure_oracle_guard(
+    ctx: Context<ConfigureOracleGuard>,
+    enabled: bool,
+    tolerance_bps: u16,
+) -> Result<()> {
+    let tolerance = if tolerance_bps == 0 {
+        ORACLE_SLIPPAGE_BPS
+    } else {
+        tolerance_bps
+    };
+    require!((tolerance as u64) < BPS_DENOMINATOR, ErrorCode::InvalidAmount);
+
+    let pool = &mut ctx.accounts.pool;
+    pool.oracle_tolerance_bps = tolerance;
+    pool.decimals_a = ctx.accounts.mint_a.decimals;
+    pool.decimals_b = ctx.accounts.mint_b.decimals;
+    if enabled {
+        pool.features_flags |= FEATURE_ORACLE_GUARD;
+    } else {
+        pool.features_flags &= !FEATURE_ORACLE_GUARD;
+    }
+    Ok(())
+}
+
 fn read_pyth(feed: &AccountInfo) -> Result<(i64, i32, i64)> {
     // Validate that the feed account is owned by Pyth program
     let pyth_program_id = Pubkey::from_str(PYTH_PROGRAM_ID).map_err(|_| error!(ErrorCode::OraclePriceInvalid))?;
     require_keys_eq!(*feed.owner, pyth_program_id, ErrorCode::OraclePriceInvalid);
 
-    let price_feed = load_price_feed_from_account_info(feed).map_err(|_| error!(ErrorCode::OraclePriceInvalid))?;
+    let price_feed = SolanaPriceAccount::account_info_to_feed(feed).map_err(|_| error!(ErrorCode::OraclePriceInvalid))?;
     let price = price_feed
         .get_price_no_older_than(Clock::get()?.unix_timestamp, 60)
         .ok_or(error!(ErrorCode::OraclePriceInvalid))?;
@@ -150,26 +192,92 @@ fn read_pyth(feed: &AccountInfo) -> Result<(i64, i32, i64)> {
     Ok((price.price, price.expo, price.publish_time))
 }
 
+/// Minimum output the oracle considers fair for `amount_in`, after applying `tolerance_bps`.
 pub fn compute_expected_out_from_oracle(
     oracle: &OracleConfig,
     amount_in: u64,
     decimals_in: u8,
     decimals_out: u8,
+    a_to_b: bool,
+    tolerance_bps: u16,
+) -> Result<u64> {
+    let expected_out = compute_oracle_out(oracle, amount_in, decimals_in, decimals_out, a_to_b)?;
+    expected_out
+        .checked_mul(BPS_DENOMINATOR - tolerance_bps as u64)
+        .and_then(|v| v.checked_div(BPS_DENOMINATOR))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Rejects swaps whose AMM output is further than `tolerance_bps` from the oracle price, in either direction.
+/// Once the guard is enabled the pool's `OracleConfig` must be supplied.
+pub fn assert_swap_within_oracle_tolerance(
+    oracle: Option<&OracleConfig>,
+    pool: &Pool,
+    amount_in: u64,
+    amount_out: u64,
+    a_to_b: bool,
+) -> Result<()> {
+    if pool.features_flags & FEATURE_ORACLE_GUARD == 0 {
+        return Ok(());
+    }
+    let oracle = oracle.ok_or(error!(ErrorCode::OracleNotConfigured))?;
+    validation::assert_staleness(
+        oracle.max_staleness_seconds,
+        Clock::get()?.unix_timestamp,
+        oracle.last_updated_ts,
+    )?;
+
+    let (decimals_in, decimals_out) = if a_to_b {
+        (pool.decimals_a, pool.decimals_b)
+    } else {
+        (pool.decimals_b, pool.decimals_a)
+    };
+    let expected_out = compute_oracle_out(oracle, amount_in, decimals_in, decimals_out, a_to_b)?;
+    let min_out = compute_expected_out_from_oracle(
+        oracle,
+        amount_in,
+        decimals_in,
+        decimals_out,
+        a_to_b,
+        pool.oracle_tolerance_bps,
+    )?;
+    let max_out = (expected_out as u128)
+        .checked_mul((BPS_DENOMINATOR + pool.oracle_tolerance_bps as u64) as u128)
+        .and_then(|v| v.checked_div(BPS_DENOMINATOR as u128))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+
+    require!(
+        amount_out >= min_out && (amount_out as u128) <= max_out,
+        ErrorCode::OracleDeviationExceeded
+    );
+    Ok(())
+}
+
+fn compute_oracle_out(
+    oracle: &OracleConfig,
+    amount_in: u64,
+    decimals_in: u8,
+    decimals_out: u8,
+    a_to_b: bool,
 ) -> Result<u64> {
     require!(oracle.price_a > 0 && oracle.price_b > 0, ErrorCode::OraclePriceInvalid);
 
-    let price_in = oracle.price_a as i128;
-    let price_out = oracle.price_b as i128;
-    let expo_diff = (oracle.expo_a as i32 - oracle.expo_b as i32)
-        .checked_add(decimals_out as i32)
+    let (price_in, expo_in, price_out, expo_out) = if a_to_b {
+        (oracle.price_a, oracle.expo_a, oracle.price_b, oracle.expo_b)
+    } else {
+        (oracle.price_b, oracle.expo_b, oracle.price_a, oracle.expo_a)
+    };
+    let expo_diff = expo_in
+        .checked_sub(expo_out)
+        .and_then(|v| v.checked_add(decimals_out as i32))
         .and_then(|v| v.checked_sub(decimals_in as i32))
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
 
     let mut numerator: i128 = amount_in as i128;
     numerator = numerator
-        .checked_mul(price_in)
+        .checked_mul(price_in as i128)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    let mut denominator: i128 = price_out;
+    let mut denominator: i128 = price_out as i128;
 
     if expo_diff >= 0 {
         let scale = math::pow10(expo_diff as u32)? as i128;
@@ -187,12 +295,7 @@ pub fn compute_expected_out_from_oracle(
     let expected_out = numerator
         .checked_div(denominator)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    let expected_out_u64: u64 = expected_out
+    expected_out
         .try_into()
-        .map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
-    let min_with_tolerance = expected_out_u64
-        .checked_mul((10000 - ORACLE_SLIPPAGE_BPS) as u64)
-        .and_then(|v| v.checked_div(10000 as u64))
-        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    Ok(min_with_tolerance)
-}
\ No newline at end of file
+        .map_err(|_| error!(ErrorCode::ArithmeticOverflow))
+}
@@ -1,30 +1,104 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use anchor_lang::solana_program::program_option::COption;
 use anchor_lang::solana_program::clock::Clock;
-use anchor_spl::token::{self, MintTo, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::metadata::{self, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3, Metadata};
+use anchor_spl::token::{self, MintTo, Token};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::{
     constants::{
-        BPS_DENOMINATOR, MAX_FEE_BPS, MIN_LIQUIDITY, MIN_REWARD_POINTS, REWARD_POINTS_FEE_BPS,
+        BPS_DENOMINATOR, LP_TOKEN_SYMBOL, MAX_FEE_BPS, MAX_LP_TOKEN_NAME_LEN, MAX_ROUTE_HOPS,
+        MIN_LIQUIDITY, MIN_REWARD_POINTS, ORACLE_SLIPPAGE_BPS, POOL_VERSION, REWARD_POINTS_FEE_BPS,
+        ROUTE_ACCOUNTS_PER_HOP, SINGLE_SIDED_SEARCH_ITERATIONS,
     },
+    circuit_breaker::{self, CircuitBreakerStrike},
+    curve,
     errors::ErrorCode,
     math,
+    oracle,
+    reentrancy,
+    registry,
     seeds,
-    state::Pool,
+    state::{
+        CurveType, FeeTiers, OracleConfig, Pool, PoolHealth, PoolObservations, PoolRegistry, PoolRegistryEntry,
+        PoolRegistryPage,
+    },
+    token_utils,
+    twap,
     validation,
 };
 
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_bps: u16,
+    pub curve_type: CurveType,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct PoolMigrated {
+    pub pool: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+// Amounts are what the vaults actually received or paid, so reserves can be replayed from them
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_minted: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_burned: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub lp_supply: u64,
+}
+
+#[event]
+pub struct Swapped {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
 #[derive(Accounts)]
+#[instruction(fee_bps: u16)]
 pub struct InitializePool<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub mint_a: Account<'info, anchor_spl::token::Mint>,
-    pub mint_b: Account<'info, anchor_spl::token::Mint>,
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         init,
         payer = payer,
         space = Pool::SIZE,
-        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref(), fee_bps.to_le_bytes().as_ref()],
         bump,
         constraint = mint_a.key() < mint_b.key() @ ErrorCode::MintOrderInvalid
     )]
@@ -34,19 +108,21 @@ pub struct InitializePool<'info> {
         payer = payer,
         token::mint = mint_a,
         token::authority = pool,
+        token::token_program = token_program_a,
         seeds = [b"pool_vault", pool.key().as_ref(), mint_a.key().as_ref()],
         bump
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = payer,
         token::mint = mint_b,
         token::authority = pool,
+        token::token_program = token_program_b,
         seeds = [b"pool_vault", pool.key().as_ref(), mint_b.key().as_ref()],
         bump
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = payer,
@@ -56,8 +132,30 @@ pub struct InitializePool<'info> {
         bump
     )]
     pub lp_mint: Account<'info, anchor_spl::token::Mint>,
+    /// CHECK: Metaplex metadata PDA for `lp_mint`, created by the metadata program
+    #[account(
+        mut,
+        seeds = [b"metadata", metadata_program.key().as_ref(), lp_mint.key().as_ref()],
+        bump,
+        seeds::program = metadata_program.key()
+    )]
+    pub lp_metadata: UncheckedAccount<'info>,
+    pub metadata_program: Program<'info, Metadata>,
+    #[account(seeds = [b"fee_tiers"], bump = fee_tiers.bump)]
+    pub fee_tiers: Box<Account<'info, FeeTiers>>,
+    #[account(mut, seeds = [b"pool_registry"], bump = registry.bump)]
+    pub registry: Box<Account<'info, PoolRegistry>>,
+    #[account(
+        mut,
+        seeds = [b"pool_registry_page", registry_page.page_index.to_le_bytes().as_ref()],
+        bump = registry_page.bump
+    )]
+    pub registry_page: Box<Account<'info, PoolRegistryPage>>,
     pub system_program: Program<'info, System>,
+    /// Program owning the LP mint; LP tokens are always classic SPL tokens
     pub token_program: Program<'info, Token>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub rent: Sysvar<'info, Rent>,
 }
 
@@ -67,7 +165,7 @@ pub struct AddLiquidity<'info> {
     pub user: Signer<'info>,
     #[account(
         mut,
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
@@ -76,13 +174,17 @@ pub struct AddLiquidity<'info> {
         address = pool.vault_a,
         constraint = vault_a.mint == pool.mint_a @ ErrorCode::InvalidVault
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         address = pool.vault_b,
         constraint = vault_b.mint == pool.mint_b @ ErrorCode::InvalidVault
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
     #[account(
         mut,
         address = pool.lp_mint,
@@ -90,17 +192,149 @@ pub struct AddLiquidity<'info> {
     )]
     pub lp_mint: Account<'info, anchor_spl::token::Mint>,
     #[account(mut)]
-    pub user_token_a: Account<'info, TokenAccount>,
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub user_token_b: Account<'info, TokenAccount>,
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
+    pub token_program: Program<'info, Token>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquiditySingle<'info> {
     #[account(mut)]
-    pub user_lp_token: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        address = pool.vault_a,
+        constraint = vault_a.mint == pool.mint_a @ ErrorCode::InvalidVault
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = pool.vault_b,
+        constraint = vault_b.mint == pool.mint_b @ ErrorCode::InvalidVault
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        address = pool.lp_mint,
+        constraint = lp_mint.mint_authority == COption::Some(pool.key()) @ ErrorCode::InvalidLpMint
+    )]
+    pub lp_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(mut)]
+    pub user_source: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle_config.bump,
+        has_one = pool
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
     pub token_program: Program<'info, Token>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
 }
 
-pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, lp_amount: u64) -> Result<()> {
+/// `min_amount_a`/`min_amount_b` bound what lands in the user's accounts, after any transfer fee.
+pub fn remove_liquidity(
+    ctx: Context<RemoveLiquidity>,
+    lp_amount: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
     require!(lp_amount > 0, ErrorCode::ZeroAmountOut);
     require!(ctx.accounts.pool.lp_supply >= lp_amount, ErrorCode::InsufficientLiquidity);
+    validation::assert_no_flash_loan(&ctx.accounts.pool)?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let (reserve_a, reserve_b) = (ctx.accounts.pool.reserve_a, ctx.accounts.pool.reserve_b);
+    let (amount_a, amount_b) =
+        compute_withdraw_amounts(lp_amount, reserve_a as u128, reserve_b as u128, ctx.accounts.pool.lp_supply)?;
+
+    token_utils::burn(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.lp_mint.to_account_info(),
+        ctx.accounts.user_lp_token.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+        lp_amount,
+    )?;
+
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&ctx.accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    let balance_before_a = ctx.accounts.user_token_a.amount;
+    token_utils::transfer_checked(
+        ctx.accounts.token_program_a.to_account_info(),
+        ctx.accounts.vault_a.to_account_info(),
+        &ctx.accounts.mint_a,
+        ctx.accounts.user_token_a.to_account_info(),
+        ctx.accounts.pool.to_account_info(),
+        &[&signer_seeds_slice],
+        amount_a,
+    )?;
+    let received_a = token_utils::received_amount(&mut ctx.accounts.user_token_a, balance_before_a)?;
+    let balance_before_b = ctx.accounts.user_token_b.amount;
+    token_utils::transfer_checked(
+        ctx.accounts.token_program_b.to_account_info(),
+        ctx.accounts.vault_b.to_account_info(),
+        &ctx.accounts.mint_b,
+        ctx.accounts.user_token_b.to_account_info(),
+        ctx.accounts.pool.to_account_info(),
+        &[&signer_seeds_slice],
+        amount_b,
+    )?;
+    let received_b = token_utils::received_amount(&mut ctx.accounts.user_token_b, balance_before_b)?;
+    require!(received_a >= min_amount_a && received_b >= min_amount_b, ErrorCode::SlippageExceeded);
+
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = ctx.accounts;
+    twap::update_cumulative_prices(&mut accounts.pool, reserve_a, reserve_b, now)?;
+    accounts.pool.lp_supply = math::sub_u64(accounts.pool.lp_supply, lp_amount)?;
+    accounts
+        .pool
+        .set_reserves(math::sub_u64(reserve_a, amount_a)?, math::sub_u64(reserve_b, amount_b)?);
+    if let Some(observations) = accounts.observations.as_mut() {
+        twap::write_observation(observations, &accounts.pool, now);
+    }
+
+    reentrancy::unlock(&mut accounts.pool);
+    emit!(LiquidityRemoved {
+        pool: accounts.pool.key(),
+        user: accounts.user.key(),
+        amount_a,
+        amount_b,
+        lp_burned: lp_amount,
+        reserve_a: accounts.pool.reserve_a,
+        reserve_b: accounts.pool.reserve_b,
+        lp_supply: accounts.pool.lp_supply,
+    });
     Ok(())
 }
 
@@ -109,7 +343,8 @@ pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
@@ -118,18 +353,56 @@ pub struct Swap<'info> {
         address = pool.vault_a,
         constraint = vault_a.mint == pool.mint_a @ ErrorCode::InvalidVault
     )]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         address = pool.vault_b,
         constraint = vault_b.mint == pool.mint_b @ ErrorCode::InvalidVault
     )]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
     #[account(mut)]
-    pub user_source: Account<'info, TokenAccount>,
+    pub user_source: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub user_destination: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
+    pub user_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: only used as the expected owner of `user_destination`
+    pub recipient: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"oracle", pool.key().as_ref()],
+        bump = oracle_config.bump,
+        has_one = pool
+    )]
+    pub oracle_config: Option<Account<'info, OracleConfig>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Pools for each hop are passed as remaining accounts, `ROUTE_ACCOUNTS_PER_HOP` at a time:
+/// `[pool (mut), vault_a (mut), vault_b (mut), mint_a, mint_b, token_program_a, token_program_b,
+/// oracle_config, observations (mut)]`, ordered from `user_source` to `user_destination`.
+#[derive(Accounts)]
+pub struct RouteSwap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub user_source: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = user_destination.owner == recipient.key() @ ErrorCode::InvalidRecipient
+    )]
+    pub user_destination: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: only used as the expected owner of `user_destination`
+    pub recipient: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -138,38 +411,98 @@ pub struct RemoveLiquidity<'info> {
     pub user: Signer<'info>,
     #[account(
         mut,
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
     #[account(mut, address = pool.vault_a)]
-    pub vault_a: Account<'info, TokenAccount>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut, address = pool.vault_b)]
-    pub vault_b: Account<'info, TokenAccount>,
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
     #[account(mut, address = pool.lp_mint)]
     pub lp_mint: Account<'info, anchor_spl::token::Mint>,
     #[account(mut)]
-    pub user_token_a: Account<'info, TokenAccount>,
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub user_token_b: Account<'info, TokenAccount>,
+    pub user_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
     #[account(mut)]
-    pub user_lp_token: Account<'info, TokenAccount>,
+    pub user_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Option<Box<Account<'info, PoolObservations>>>,
     pub token_program: Program<'info, Token>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
 pub struct HealthCheck<'info> {
     #[account(
-        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()],
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
         bump = pool.bump
     )]
     pub pool: Account<'info, Pool>,
+    #[account(address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, token::Mint>,
+}
+
+/// `pool` may still be on a shorter layout that `Account<Pool>` refuses to load, so it is taken
+/// unchecked and decoded by `upgrade_pool_data`; the remaining accounts are matched against it
+/// once decoded.
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: program-owned; discriminator and layout are verified by `upgrade_pool_data`
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Values a v1 pool never stored, read from the accounts passed to `migrate_pool`.
+pub(crate) struct PoolMigrationInputs {
+    pub decimals_a: u8,
+    pub decimals_b: u8,
+    pub balance_a: u64,
+    pub balance_b: u64,
+    pub now: i64,
 }
 
-pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+pub fn initialize_pool(
+    ctx: Context<InitializePool>,
+    fee_bps: u16,
+    curve_type: CurveType,
+    amp: u64,
+    weight_a_bps: u16,
+    symbol_a: String,
+    symbol_b: String,
+) -> Result<()> {
     validation::assert_positive(fee_bps.into())?;
     require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+    require!(ctx.accounts.fee_tiers.tiers.contains(&fee_bps), ErrorCode::FeeTierNotAllowed);
     assert_mint_order(&ctx.accounts.mint_a.key(), &ctx.accounts.mint_b.key())?;
+    curve::validate_curve(curve_type, amp, weight_a_bps)?;
+    token_utils::assert_supported_mint(&ctx.accounts.mint_a)?;
+    token_utils::assert_supported_mint(&ctx.accounts.mint_b)?;
+    if curve_type == CurveType::StableSwap {
+        curve::decimal_scales(ctx.accounts.mint_a.decimals, ctx.accounts.mint_b.decimals)?;
+    }
 
     let pool = &mut ctx.accounts.pool;
     pool.mint_a = ctx.accounts.mint_a.key();
@@ -189,18 +522,123 @@ pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()>
     pool.max_daily_volume = 0;
     pool.current_daily_volume = 0;
     pool.last_volume_reset = Clock::get()?.unix_timestamp;
-    pool.version = 1;
+    pool.version = POOL_VERSION;
     pool.features_flags = 0;
     pool.reward_points = 0;
     pool.swap_count = 0;
     pool.last_reward_claim_ts = 0;
-    pool._reserved = [0; 32];
+    pool.oracle_tolerance_bps = ORACLE_SLIPPAGE_BPS;
+    pool.decimals_a = ctx.accounts.mint_a.decimals;
+    pool.decimals_b = ctx.accounts.mint_b.decimals;
+    pool.price_a_cumulative = 0;
+    pool.price_b_cumulative = 0;
+    pool.last_price_update_ts = pool.created_at;
+    pool.curve_type = curve_type;
+    pool.amp = amp;
+    pool.weight_a_bps = if curve_type == CurveType::Weighted {
+        weight_a_bps
+    } else {
+        (BPS_DENOMINATOR / 2) as u16
+    };
+    pool.flash_loan_active = false;
+    pool.flash_repay_a = 0;
+    pool.flash_repay_b = 0;
+    pool.k_last = 0;
+    pool.reserve_a = 0;
+    pool.reserve_b = 0;
+    pool.locked = false;
+    pool.reward_per_share = 0;
+    pool.staked_lp = 0;
+    pool.max_price_impact_bps = 0;
+    pool.max_window_move_bps = 0;
+    pool.window_start_ts = 0;
+    pool.window_start_price = 0;
+    pool.breaker_violations = 0;
+    pool.legacy_seeds = false;
+    pool._reserved = [0; 98];
+
+    let entry = PoolRegistryEntry {
+        pool: pool.key(),
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        fee_bps: pool.fee_bps,
+        curve_type: pool.curve_type,
+    };
+    registry::register_pool(&mut ctx.accounts.registry, &mut ctx.accounts.registry_page, entry)?;
+
+    let lp_name = lp_token_name(&symbol_a, &symbol_b)?;
+    create_lp_metadata(ctx.accounts, lp_name)?;
+
+    let pool = &ctx.accounts.pool;
+    emit!(PoolCreated {
+        pool: pool.key(),
+        mint_a: pool.mint_a,
+        mint_b: pool.mint_b,
+        lp_mint: pool.lp_mint,
+        fee_bps: pool.fee_bps,
+        curve_type: pool.curve_type,
+        creator: pool.creator,
+    });
     Ok(())
 }
 
-pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: u64, amount_b: u64) -> Result<()> {
+/// Names the LP token after its pair, e.g. `"AEGIS-LP SOL/USDC"`.
+fn lp_token_name(symbol_a: &str, symbol_b: &str) -> Result<String> {
+    let is_valid_symbol =
+        |symbol: &str| !symbol.is_empty() && symbol.chars().all(|c| c.is_ascii_graphic() && c != '/');
+    require!(
+        is_valid_symbol(symbol_a) && is_valid_symbol(symbol_b),
+        ErrorCode::InvalidLpMetadata
+    );
+    let name = format!("{} {}/{}", LP_TOKEN_SYMBOL, symbol_a, symbol_b);
+    require!(name.len() <= MAX_LP_TOKEN_NAME_LEN, ErrorCode::InvalidLpMetadata);
+    Ok(name)
+}
+
+fn create_lp_metadata(accounts: &InitializePool, name: String) -> Result<()> {
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    metadata::create_metadata_accounts_v3(
+        CpiContext::new_with_signer(
+            accounts.metadata_program.to_account_info(),
+            CreateMetadataAccountsV3 {
+                metadata: accounts.lp_metadata.to_account_info(),
+                mint: accounts.lp_mint.to_account_info(),
+                mint_authority: accounts.pool.to_account_info(),
+                payer: accounts.payer.to_account_info(),
+                update_authority: accounts.pool.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                rent: accounts.rent.to_account_info(),
+            },
+            &[&signer_seeds_slice],
+        ),
+        DataV2 {
+            name,
+            symbol: LP_TOKEN_SYMBOL.to_string(),
+            uri: String::new(),
+            seller_fee_basis_points: 0,
+            creators: None,
+            collection: None,
+            uses: None,
+        },
+        true,
+        true,
+        None,
+    )
+}
+
+pub fn add_liquidity(
+    ctx: Context<AddLiquidity>,
+    amount_a: u64,
+    amount_b: u64,
+    min_lp_out: u64,
+    max_ratio_deviation_bps: Option<u16>,
+) -> Result<()> {
     validation::assert_positive(amount_a)?;
     validation::assert_positive(amount_b)?;
+    validation::assert_positive(min_lp_out)?;
+    validation::assert_no_flash_loan(&ctx.accounts.pool)?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
 
     let pool = &ctx.accounts.pool;
     require!(
@@ -208,35 +646,154 @@ pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: u64, amount_b: u64) -
         ErrorCode::InvalidLpMint
     );
 
-    let reserve_a = ctx.accounts.vault_a.amount as u128;
-    let reserve_b = ctx.accounts.vault_b.amount as u128;
+    let reserve_a = pool.reserve_a as u128;
+    let reserve_b = pool.reserve_b as u128;
+    let (balance_a, balance_b) = (ctx.accounts.vault_a.amount, ctx.accounts.vault_b.amount);
 
-    let (used_a, used_b, minted) = compute_liquidity_mint(amount_a, amount_b, reserve_a, reserve_b, pool.lp_supply)?;
+    if let Some(max_deviation_bps) = max_ratio_deviation_bps {
+        if pool.lp_supply > 0 {
+            assert_deposit_ratio(amount_a, amount_b, reserve_a, reserve_b, max_deviation_bps)?;
+        }
+    }
 
-    token::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_token_a.to_account_info(),
-                to: ctx.accounts.vault_a.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
-            },
-        ),
+    let (used_a, used_b, _) =
+        compute_liquidity_mint(amount_a, amount_b, reserve_a, reserve_b, pool.lp_supply, pool.weight_a_bps)?;
+
+    token_utils::transfer_checked(
+        ctx.accounts.token_program_a.to_account_info(),
+        ctx.accounts.user_token_a.to_account_info(),
+        &ctx.accounts.mint_a,
+        ctx.accounts.vault_a.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+        &[],
         used_a,
     )?;
+    token_utils::transfer_checked(
+        ctx.accounts.token_program_b.to_account_info(),
+        ctx.accounts.user_token_b.to_account_info(),
+        &ctx.accounts.mint_b,
+        ctx.accounts.vault_b.to_account_info(),
+        ctx.accounts.user.to_account_info(),
+        &[],
+        used_b,
+    )?;
 
-    token::transfer(
-        CpiContext::new(
+    // Mint against what the vaults actually received so transfer fees are borne by the depositor
+    let received_a = token_utils::received_amount(&mut ctx.accounts.vault_a, balance_a)?;
+    let received_b = token_utils::received_amount(&mut ctx.accounts.vault_b, balance_b)?;
+    let pool = &ctx.accounts.pool;
+    let (_, _, minted) =
+        compute_liquidity_mint(received_a, received_b, reserve_a, reserve_b, pool.lp_supply, pool.weight_a_bps)?;
+    require!(minted >= min_lp_out, ErrorCode::SlippageExceeded);
+
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    token::mint_to(
+        CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_token_b.to_account_info(),
-                to: ctx.accounts.vault_b.to_account_info(),
-                authority: ctx.accounts.user.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.user_lp_token.to_account_info(),
+                authority: ctx.accounts.pool.to_account_info(),
             },
+            &[&signer_seeds_slice],
         ),
-        used_b,
+        minted,
     )?;
 
+    let now = Clock::get()?.unix_timestamp;
+    let accounts = ctx.accounts;
+    twap::update_cumulative_prices(&mut accounts.pool, reserve_a as u64, reserve_b as u64, now)?;
+    accounts.pool.lp_supply = math::add_u64(accounts.pool.lp_supply, minted)?;
+    accounts.pool.set_reserves(
+        math::add_u64(reserve_a as u64, received_a)?,
+        math::add_u64(reserve_b as u64, received_b)?,
+    );
+    if let Some(observations) = accounts.observations.as_mut() {
+        twap::write_observation(observations, &accounts.pool, now);
+    }
+
+    reentrancy::unlock(&mut accounts.pool);
+    emit!(LiquidityAdded {
+        pool: accounts.pool.key(),
+        user: accounts.user.key(),
+        amount_a: received_a,
+        amount_b: received_b,
+        lp_minted: minted,
+        reserve_a: accounts.pool.reserve_a,
+        reserve_b: accounts.pool.reserve_b,
+        lp_supply: accounts.pool.lp_supply,
+    });
+    Ok(())
+}
+
+/// Deposits a single token: part of `amount_in` is priced as a swap into the other side so the
+/// remainder and the swap output match the post-swap reserve ratio, then LP is minted for both.
+/// The swapped tokens never leave the pool, so only the input vault receives a transfer; rounding
+/// dust left over by the split stays in the pool.
+pub fn add_liquidity_single(
+    ctx: Context<AddLiquiditySingle>,
+    amount_in: u64,
+    is_a: bool,
+    min_lp_out: u64,
+) -> Result<()> {
+    validation::assert_positive(amount_in)?;
+    validation::assert_positive(min_lp_out)?;
+    validation::assert_no_flash_loan(&ctx.accounts.pool)?;
+    validation::assert_not_emergency(&ctx.accounts.pool)?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let pool = &ctx.accounts.pool;
+    let input_mint = if is_a { pool.mint_a } else { pool.mint_b };
+    require_keys_eq!(ctx.accounts.user_source.mint, input_mint, ErrorCode::InvalidVault);
+
+    let (reserve_a, reserve_b) = (pool.reserve_a, pool.reserve_b);
+    // Price the swap on what arrives after any transfer fee
+    let received = {
+        let accounts = &mut *ctx.accounts;
+        let (token_program, mint, input_vault) = if is_a {
+            (&accounts.token_program_a, &accounts.mint_a, &mut accounts.vault_a)
+        } else {
+            (&accounts.token_program_b, &accounts.mint_b, &mut accounts.vault_b)
+        };
+        let balance_before = input_vault.amount;
+        token_utils::transfer_checked(
+            token_program.to_account_info(),
+            accounts.user_source.to_account_info(),
+            mint,
+            input_vault.to_account_info(),
+            accounts.user.to_account_info(),
+            &[],
+            amount_in,
+        )?;
+        token_utils::received_amount(input_vault, balance_before)?
+    };
+
+    let pool = &ctx.accounts.pool;
+    let (swap_amount, _, minted) =
+        compute_single_sided_mint(pool, received, is_a, reserve_a as u128, reserve_b as u128)?;
+    require!(minted >= min_lp_out, ErrorCode::SlippageExceeded);
+
+    // The internal swap gets the same oracle and breaker checks as `swap`, with the breaker
+    // measuring the reserves the whole deposit leaves behind
+    let now = Clock::get()?.unix_timestamp;
+    let (amount_a, amount_b) = if is_a { (received, 0) } else { (0, received) };
+    if swap_amount > 0 {
+        let (reserve_in, reserve_out) = swap_reserves(pool, is_a);
+        let swap_out = pool_swap_out(pool, swap_amount, reserve_in, reserve_out, is_a)?;
+        oracle::assert_swap_within_oracle_tolerance(
+            ctx.accounts.oracle_config.as_deref(),
+            pool,
+            swap_amount,
+            swap_out,
+            is_a,
+        )?;
+        let post_reserves = (math::add_u64(reserve_a, amount_a)?, math::add_u64(reserve_b, amount_b)?);
+        let user = ctx.accounts.user.key();
+        check_circuit_breaker(&mut ctx.accounts.pool, user, is_a, swap_amount, post_reserves, now)?;
+    }
+
+    let pool = &ctx.accounts.pool;
     let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(pool);
     let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
     token::mint_to(
@@ -252,103 +809,896 @@ pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: u64, amount_b: u64) -
         minted,
     )?;
 
-    let pool = &mut ctx.accounts.pool;
-    pool.lp_supply = math::add_u64(pool.lp_supply, minted)?;
+    let accounts = ctx.accounts;
+    twap::update_cumulative_prices(&mut accounts.pool, reserve_a, reserve_b, now)?;
+    if swap_amount > 0 {
+        record_swap(&mut accounts.pool, swap_amount)?;
+    }
+    accounts.pool.lp_supply = math::add_u64(accounts.pool.lp_supply, minted)?;
+    accounts
+        .pool
+        .set_reserves(math::add_u64(reserve_a, amount_a)?, math::add_u64(reserve_b, amount_b)?);
+    if let Some(observations) = accounts.observations.as_mut() {
+        twap::write_observation(observations, &accounts.pool, now);
+    }
+
+    reentrancy::unlock(&mut accounts.pool);
+    emit!(LiquidityAdded {
+        pool: accounts.pool.key(),
+        user: accounts.user.key(),
+        amount_a,
+        amount_b,
+        lp_minted: minted,
+        reserve_a: accounts.pool.reserve_a,
+        reserve_b: accounts.pool.reserve_b,
+        lp_supply: accounts.pool.lp_supply,
+    });
     Ok(())
 }
 
-pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64, a_to_b: bool) -> Result<()> {
+pub fn swap(
+    ctx: Context<Swap>,
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+    deadline: i64,
+) -> Result<()> {
     validation::assert_positive(amount_in)?;
     validation::assert_positive(min_amount_out)?;
+    validation::assert_deadline(deadline, Clock::get()?.unix_timestamp)?;
+    validate_swap_accounts(ctx.accounts, a_to_b)?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (user, user_source) = (ctx.accounts.user.to_account_info(), ctx.accounts.user_source.to_account_info());
+    let (mut pool_swap, user_destination) = split_swap_accounts(ctx.accounts);
+    let received_in = pool_swap.transfer_in(a_to_b, user_source, user.clone(), &[], amount_in)?;
+    let amount_out = pool_swap.quote_out(a_to_b, received_in)?;
+    pool_swap.settle(user.key(), a_to_b, received_in, amount_out, now)?;
+
+    let delivered = pool_swap.pay_out(a_to_b, user_destination, amount_out)?;
+    require!(delivered >= min_amount_out, ErrorCode::SlippageExceeded);
+    reentrancy::unlock(pool_swap.pool);
+    Ok(())
+}
+
+pub fn swap_exact_out(
+    ctx: Context<Swap>,
+    amount_out: u64,
+    max_amount_in: u64,
+    a_to_b: bool,
+    deadline: i64,
+) -> Result<()> {
+    validation::assert_positive(amount_out)?;
+    validation::assert_positive(max_amount_in)?;
+    validation::assert_deadline(deadline, Clock::get()?.unix_timestamp)?;
+    validate_swap_accounts(ctx.accounts, a_to_b)?;
+    reentrancy::lock(&mut ctx.accounts.pool, ctx.program_id)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let (user, user_source) = (ctx.accounts.user.to_account_info(), ctx.accounts.user_source.to_account_info());
+    let (mut pool_swap, user_destination) = split_swap_accounts(ctx.accounts);
+    let (reserve_in, reserve_out) = swap_reserves(pool_swap.pool, a_to_b);
+    let (input_mint, output_mint) = if a_to_b {
+        (pool_swap.mint_a, pool_swap.mint_b)
+    } else {
+        (pool_swap.mint_b, pool_swap.mint_a)
+    };
+    // Gross up both legs so the recipient nets `amount_out` after any transfer fees
+    let gross_out = token_utils::gross_up_for_transfer_fee(output_mint, amount_out)?;
+    let net_in = pool_swap_in(pool_swap.pool, gross_out, reserve_in, reserve_out, a_to_b)?;
+    let amount_in = token_utils::gross_up_for_transfer_fee(input_mint, net_in)?;
+    require!(amount_in <= max_amount_in, ErrorCode::SlippageExceeded);
+
+    let received_in = pool_swap.transfer_in(a_to_b, user_source, user.clone(), &[], amount_in)?;
+    require!(received_in >= net_in, ErrorCode::SlippageExceeded);
+    pool_swap.settle(user.key(), a_to_b, received_in, gross_out, now)?;
+
+    let delivered = pool_swap.pay_out(a_to_b, user_destination, gross_out)?;
+    require!(delivered >= amount_out, ErrorCode::SlippageExceeded);
+    reentrancy::unlock(pool_swap.pool);
+    Ok(())
+}
+
+fn validate_swap_accounts(accounts: &Swap, a_to_b: bool) -> Result<()> {
+    validation::assert_no_flash_loan(&accounts.pool)?;
+    validation::assert_not_emergency(&accounts.pool)?;
+    let (input_mint, output_mint) = if a_to_b {
+        (accounts.pool.mint_a, accounts.pool.mint_b)
+    } else {
+        (accounts.pool.mint_b, accounts.pool.mint_a)
+    };
+    require_keys_eq!(accounts.user_source.mint, input_mint, ErrorCode::InvalidVault);
+    require_keys_eq!(
+        accounts.user_destination.mint,
+        output_mint,
+        ErrorCode::InvalidDestinationMint
+    );
+    require_keys_eq!(
+        accounts.user_destination.owner,
+        accounts.recipient.key(),
+        ErrorCode::InvalidRecipient
+    );
+    Ok(())
+}
+
+/// Splits `Swap` into the pool's side of the trade and the account receiving its output.
+fn split_swap_accounts<'a, 'info>(
+    accounts: &'a mut Swap<'info>,
+) -> (PoolSwap<'a, 'info>, &'a mut InterfaceAccount<'info, TokenAccount>) {
+    let pool_swap = PoolSwap {
+        pool: &mut accounts.pool,
+        vault_a: &mut accounts.vault_a,
+        vault_b: &mut accounts.vault_b,
+        mint_a: &accounts.mint_a,
+        mint_b: &accounts.mint_b,
+        token_program_a: &accounts.token_program_a,
+        token_program_b: &accounts.token_program_b,
+        oracle_config: accounts.oracle_config.as_deref(),
+        observations: accounts.observations.as_deref_mut().map(|observations| &mut **observations),
+    };
+    (pool_swap, &mut accounts.user_destination)
+}
+
+fn swap_reserves(pool: &Pool, a_to_b: bool) -> (u128, u128) {
+    if a_to_b {
+        (pool.reserve_a as u128, pool.reserve_b as u128)
+    } else {
+        (pool.reserve_b as u128, pool.reserve_a as u128)
+    }
+}
+
+/// Books a completed swap: the input vault gained `amount_in`, the output vault paid `amount_out`.
+fn apply_swap_to_reserves(pool: &mut Pool, a_to_b: bool, amount_in: u64, amount_out: u64) -> Result<()> {
+    let (reserve_a, reserve_b) = post_swap_reserves(pool, a_to_b, amount_in, amount_out)?;
+    pool.set_reserves(reserve_a, reserve_b);
+    Ok(())
+}
+
+pub(crate) fn post_swap_reserves(pool: &Pool, a_to_b: bool, amount_in: u64, amount_out: u64) -> Result<(u64, u64)> {
+    if a_to_b {
+        Ok((math::add_u64(pool.reserve_a, amount_in)?, math::sub_u64(pool.reserve_b, amount_out)?))
+    } else {
+        Ok((math::sub_u64(pool.reserve_a, amount_out)?, math::add_u64(pool.reserve_b, amount_in)?))
+    }
+}
+
+/// Runs a trade that leaves the pool at `post_reserves` past the circuit breaker, emitting
+/// `CircuitBreakerStrike` when it counts as a strike.
+fn check_circuit_breaker(
+    pool: &mut Account<Pool>,
+    user: Pubkey,
+    a_to_b: bool,
+    amount_in: u64,
+    post_reserves: (u64, u64),
+    now: i64,
+) -> Result<()> {
+    let (post_a, post_b) = post_reserves;
+    if let Some(price_move) = circuit_breaker::check_swap(pool, post_a, post_b, now)? {
+        emit!(CircuitBreakerStrike {
+            pool: pool.key(),
+            user,
+            a_to_b,
+            amount_in,
+            price_impact_bps: price_move.price_impact_bps,
+            window_move_bps: price_move.window_move_bps,
+            violations: pool.breaker_violations,
+            emergency_mode: pool.emergency_mode,
+        });
+    }
+    Ok(())
+}
+
+/// One pool's side of a swap, shared by direct swaps, every hop of a route and escrow-funded
+/// orders so each of them gets the same checks and bookkeeping. The caller holds the pool's
+/// reentrancy lock from the input transfer until the output is paid.
+pub(crate) struct PoolSwap<'a, 'info> {
+    pub pool: &'a mut Account<'info, Pool>,
+    pub vault_a: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub vault_b: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub mint_a: &'a InterfaceAccount<'info, Mint>,
+    pub mint_b: &'a InterfaceAccount<'info, Mint>,
+    pub token_program_a: &'a Interface<'info, TokenInterface>,
+    pub token_program_b: &'a Interface<'info, TokenInterface>,
+    pub oracle_config: Option<&'a OracleConfig>,
+    pub observations: Option<&'a mut PoolObservations>,
+}
 
-    let result = (|| {
-        let pool = &ctx.accounts.pool;
-        let (input_vault, output_vault) = if a_to_b {
-            (&ctx.accounts.vault_a, &ctx.accounts.vault_b)
+impl<'info> PoolSwap<'_, 'info> {
+    /// Moves `amount_in` from `source` into the input vault and returns how much actually arrived.
+    pub fn transfer_in(
+        &mut self,
+        a_to_b: bool,
+        source: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount_in: u64,
+    ) -> Result<u64> {
+        let (token_program, mint, input_vault) = if a_to_b {
+            (self.token_program_a, self.mint_a, &mut *self.vault_a)
         } else {
-            (&ctx.accounts.vault_b, &ctx.accounts.vault_a)
+            (self.token_program_b, self.mint_b, &mut *self.vault_b)
         };
-
-        let amount_out = compute_swap_out(
+        let balance_before = input_vault.amount;
+        token_utils::transfer_checked(
+            token_program.to_account_info(),
+            source,
+            mint,
+            input_vault.to_account_info(),
+            authority,
+            signer_seeds,
             amount_in,
-            input_vault.amount as u128,
-            output_vault.amount as u128,
-            pool.fee_bps,
         )?;
-        require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
-
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_source.to_account_info(),
-                    to: input_vault.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
+        token_utils::received_amount(input_vault, balance_before)
+    }
+
+    /// Output the pool pays for `amount_in` at its current reserves.
+    pub fn quote_out(&self, a_to_b: bool, amount_in: u64) -> Result<u64> {
+        let (reserve_in, reserve_out) = swap_reserves(self.pool, a_to_b);
+        pool_swap_out(self.pool, amount_in, reserve_in, reserve_out, a_to_b)
+    }
+
+    /// Checks a priced swap against the oracle and the circuit breaker and books it on the pool:
+    /// reward points, reserves, TWAP accumulators and observations, then `Swapped`. Runs once the
+    /// input is in the vault and before the output is paid.
+    pub fn settle(&mut self, user: Pubkey, a_to_b: bool, amount_in: u64, amount_out: u64, now: i64) -> Result<()> {
+        oracle::assert_swap_within_oracle_tolerance(self.oracle_config, self.pool, amount_in, amount_out, a_to_b)?;
+        let (reserve_a, reserve_b) = (self.pool.reserve_a, self.pool.reserve_b);
+        let (post_a, post_b) = post_swap_reserves(self.pool, a_to_b, amount_in, amount_out)?;
+        check_circuit_breaker(self.pool, user, a_to_b, amount_in, (post_a, post_b), now)?;
+        let pool = &mut **self.pool;
+        record_swap(pool, amount_in)?;
+        apply_swap_to_reserves(pool, a_to_b, amount_in, amount_out)?;
+        // Accumulate prices over the pre-trade reserves
+        twap::update_cumulative_prices(pool, reserve_a, reserve_b, now)?;
+        if let Some(observations) = self.observations.as_deref_mut() {
+            twap::write_observation(observations, pool, now);
+        }
+        emit!(Swapped {
+            pool: self.pool.key(),
+            user,
+            a_to_b,
             amount_in,
+            amount_out,
+            fee: swap_fee_amount(amount_in, self.pool.fee_bps)?,
+            reserve_a: self.pool.reserve_a,
+            reserve_b: self.pool.reserve_b,
+        });
+        Ok(())
+    }
+
+    /// Pays `amount` from the output vault and returns how much `destination` actually received;
+    /// zero amounts are skipped.
+    pub fn pay_out(
+        &self,
+        a_to_b: bool,
+        destination: &mut InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+    ) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+        let (token_program, mint, output_vault) = if a_to_b {
+            (self.token_program_b, self.mint_b, &*self.vault_b)
+        } else {
+            (self.token_program_a, self.mint_a, &*self.vault_a)
+        };
+        let balance_before = destination.amount;
+        let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(self.pool);
+        let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+        token_utils::transfer_checked(
+            token_program.to_account_info(),
+            output_vault.to_account_info(),
+            mint,
+            destination.to_account_info(),
+            self.pool.to_account_info(),
+            &[&signer_seeds_slice],
+            amount,
         )?;
+        token_utils::received_amount(destination, balance_before)
+    }
+}
+
+/// Pool side of a swap funded from a pool-owned order escrow, shared by limit and DCA orders.
+/// The caller holds the pool's reentrancy lock around `execute` and `pay_out`.
+pub(crate) struct EscrowSwap<'a, 'info> {
+    pub pool: &'a mut Account<'info, Pool>,
+    pub escrow: &'a InterfaceAccount<'info, TokenAccount>,
+    pub vault_a: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub vault_b: &'a mut InterfaceAccount<'info, TokenAccount>,
+    pub mint_a: &'a InterfaceAccount<'info, Mint>,
+    pub mint_b: &'a InterfaceAccount<'info, Mint>,
+    pub token_program_a: &'a Interface<'info, TokenInterface>,
+    pub token_program_b: &'a Interface<'info, TokenInterface>,
+    pub oracle_config: Option<&'a OracleConfig>,
+    pub observations: Option<&'a mut PoolObservations>,
+}
 
-        let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(pool);
+impl<'info> EscrowSwap<'_, 'info> {
+    /// Moves `amount_in` from the escrow into the input vault, prices what arrived and books the
+    /// swap on the pool. Returns `(received_in, amount_out)`; the output stays in the vault until
+    /// `pay_out` distributes it.
+    pub fn execute(&mut self, user: Pubkey, a_to_b: bool, amount_in: u64, now: i64) -> Result<(u64, u64)> {
+        validation::assert_not_emergency(self.pool)?;
+        let (escrow, authority) = (self.escrow.to_account_info(), self.pool.to_account_info());
+        let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(self.pool);
         let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: output_vault.to_account_info(),
-                    to: ctx.accounts.user_destination.to_account_info(),
-                    authority: ctx.accounts.pool.to_account_info(),
-                },
-                &[&signer_seeds_slice],
-            ),
+        let mut pool_swap = self.pool_swap();
+        let received_in = pool_swap.transfer_in(a_to_b, escrow, authority, &[&signer_seeds_slice], amount_in)?;
+        let amount_out = pool_swap.quote_out(a_to_b, received_in)?;
+        pool_swap.settle(user, a_to_b, received_in, amount_out, now)?;
+        Ok((received_in, amount_out))
+    }
+
+    /// Pays `amount` of a swap's output from the output vault and returns how much `destination`
+    /// actually received; zero amounts are skipped.
+    pub fn pay_out(
+        &mut self,
+        a_to_b: bool,
+        destination: &mut InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+    ) -> Result<u64> {
+        self.pool_swap().pay_out(a_to_b, destination, amount)
+    }
+
+    fn pool_swap(&mut self) -> PoolSwap<'_, 'info> {
+        PoolSwap {
+            pool: &mut *self.pool,
+            vault_a: &mut *self.vault_a,
+            vault_b: &mut *self.vault_b,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            token_program_a: self.token_program_a,
+            token_program_b: self.token_program_b,
+            oracle_config: self.oracle_config,
+            observations: self.observations.as_deref_mut(),
+        }
+    }
+}
+
+// Update pool state only once the swap transfers succeeded
+fn record_swap(pool: &mut Pool, amount_in: u64) -> Result<()> {
+    let fee_points = (amount_in as u128)
+        .checked_mul(REWARD_POINTS_FEE_BPS as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let effective_points = fee_points.max(MIN_REWARD_POINTS);
+    pool.reward_points = pool
+        .reward_points
+        .checked_add(effective_points)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    pool.swap_count = pool
+        .swap_count
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    Ok(())
+}
+
+pub fn route_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+    amount_in: u64,
+    min_amount_out: u64,
+    deadline: i64,
+) -> Result<()> {
+    validation::assert_positive(amount_in)?;
+    validation::assert_positive(min_amount_out)?;
+    validation::assert_deadline(deadline, Clock::get()?.unix_timestamp)?;
+
+    let remaining = ctx.remaining_accounts;
+    require!(
+        !remaining.is_empty() && remaining.len().is_multiple_of(ROUTE_ACCOUNTS_PER_HOP),
+        ErrorCode::InvalidRoute
+    );
+    require!(
+        remaining.len() / ROUTE_ACCOUNTS_PER_HOP <= MAX_ROUTE_HOPS,
+        ErrorCode::InvalidRoute
+    );
+
+    // Load, validate and lock every hop before moving any tokens
+    let mut hops: Vec<RouteHop<'info>> = Vec::with_capacity(remaining.len() / ROUTE_ACCOUNTS_PER_HOP);
+    let mut current_mint = ctx.accounts.user_source.mint;
+    for chunk in remaining.chunks(ROUTE_ACCOUNTS_PER_HOP) {
+        let hop = load_route_hop(ctx.program_id, chunk, current_mint)?;
+        require!(
+            hops.iter().all(|h| h.pool.key() != hop.pool.key()),
+            ErrorCode::InvalidRoute
+        );
+        current_mint = if hop.a_to_b { hop.pool.mint_b } else { hop.pool.mint_a };
+        hops.push(hop);
+    }
+    require_keys_eq!(
+        ctx.accounts.user_destination.mint,
+        current_mint,
+        ErrorCode::InvalidDestinationMint
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let user = ctx.accounts.user.to_account_info();
+    let first = &mut hops[0];
+    let a_to_b = first.a_to_b;
+    let mut amount = first
+        .pool_swap()
+        .transfer_in(a_to_b, ctx.accounts.user_source.to_account_info(), user.clone(), &[], amount_in)?;
+
+    // Each hop is priced on what actually reached its vault and pays straight into the next
+    // pool's input vault
+    for i in 0..hops.len() {
+        let (settled, rest) = hops.split_at_mut(i + 1);
+        let hop = &mut settled[i];
+        let a_to_b = hop.a_to_b;
+        let mut pool_swap = hop.pool_swap();
+        let amount_out = pool_swap.quote_out(a_to_b, amount)?;
+        pool_swap.settle(user.key(), a_to_b, amount, amount_out, now)?;
+        let destination = match rest.first_mut() {
+            Some(next) => next.input_vault(),
+            None => &mut ctx.accounts.user_destination,
+        };
+        amount = pool_swap.pay_out(a_to_b, destination, amount_out)?;
+    }
+    require!(amount >= min_amount_out, ErrorCode::SlippageExceeded);
+
+    for hop in hops.iter_mut() {
+        reentrancy::unlock(&mut hop.pool);
+        hop.pool.exit(ctx.program_id)?;
+        if let Some(observations) = &hop.observations {
+            observations.exit(ctx.program_id)?;
+        }
+    }
+    Ok(())
+}
+
+struct RouteHop<'info> {
+    pool: Box<Account<'info, Pool>>,
+    vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    mint_a: Box<InterfaceAccount<'info, Mint>>,
+    mint_b: Box<InterfaceAccount<'info, Mint>>,
+    token_program_a: Interface<'info, TokenInterface>,
+    token_program_b: Interface<'info, TokenInterface>,
+    oracle_config: Option<Box<Account<'info, OracleConfig>>>,
+    observations: Option<Box<Account<'info, PoolObservations>>>,
+    a_to_b: bool,
+}
+
+impl<'info> RouteHop<'info> {
+    fn pool_swap(&mut self) -> PoolSwap<'_, 'info> {
+        PoolSwap {
+            pool: &mut self.pool,
+            vault_a: &mut self.vault_a,
+            vault_b: &mut self.vault_b,
+            mint_a: &self.mint_a,
+            mint_b: &self.mint_b,
+            token_program_a: &self.token_program_a,
+            token_program_b: &self.token_program_b,
+            oracle_config: self.oracle_config.as_deref().map(|oracle_config| &**oracle_config),
+            observations: self.observations.as_deref_mut().map(|observations| &mut **observations),
+        }
+    }
+
+    fn input_vault(&mut self) -> &mut InterfaceAccount<'info, TokenAccount> {
+        if self.a_to_b {
+            &mut self.vault_a
+        } else {
+            &mut self.vault_b
+        }
+    }
+}
+
+/// Loads one hop's `ROUTE_ACCOUNTS_PER_HOP` accounts and takes the pool's reentrancy lock. The
+/// optional oracle and observations slots take the program id when a pool has none.
+fn load_route_hop<'info>(
+    program_id: &Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    input_mint: Pubkey,
+) -> Result<RouteHop<'info>> {
+    let [
+        pool_info,
+        vault_a_info,
+        vault_b_info,
+        mint_a_info,
+        mint_b_info,
+        token_program_a_info,
+        token_program_b_info,
+        oracle_info,
+        observations_info,
+    ] = accounts
+    else {
+        return err!(ErrorCode::InvalidRoute);
+    };
+    require!(
+        pool_info.is_writable && vault_a_info.is_writable && vault_b_info.is_writable,
+        ErrorCode::InvalidRoute
+    );
+    let mut pool: Box<Account<'info, Pool>> = Box::new(Account::try_from(pool_info)?);
+    validation::assert_no_flash_loan(&pool)?;
+    validation::assert_not_emergency(&pool)?;
+    reentrancy::lock(&mut pool, program_id)?;
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    let expected_pool = Pubkey::create_program_address(&signer_seeds_slice, program_id)
+        .map_err(|_| error!(ErrorCode::InvalidRoute))?;
+    require_keys_eq!(pool.key(), expected_pool, ErrorCode::InvalidRoute);
+
+    require_keys_eq!(vault_a_info.key(), pool.vault_a, ErrorCode::InvalidVault);
+    require_keys_eq!(vault_b_info.key(), pool.vault_b, ErrorCode::InvalidVault);
+    require_keys_eq!(mint_a_info.key(), pool.mint_a, ErrorCode::InvalidRoute);
+    require_keys_eq!(mint_b_info.key(), pool.mint_b, ErrorCode::InvalidRoute);
+    require_keys_eq!(*mint_a_info.owner, token_program_a_info.key(), ErrorCode::InvalidRoute);
+    require_keys_eq!(*mint_b_info.owner, token_program_b_info.key(), ErrorCode::InvalidRoute);
+    let vault_a: Box<InterfaceAccount<'info, TokenAccount>> = Box::new(InterfaceAccount::try_from(vault_a_info)?);
+    let vault_b: Box<InterfaceAccount<'info, TokenAccount>> = Box::new(InterfaceAccount::try_from(vault_b_info)?);
+    require_keys_eq!(vault_a.mint, pool.mint_a, ErrorCode::InvalidVault);
+    require_keys_eq!(vault_b.mint, pool.mint_b, ErrorCode::InvalidVault);
+
+    let oracle_config = if oracle_info.key() == *program_id {
+        None
+    } else {
+        let oracle_config: Box<Account<'info, OracleConfig>> = Box::new(Account::try_from(oracle_info)?);
+        require_keys_eq!(oracle_config.pool, pool.key(), ErrorCode::InvalidRoute);
+        Some(oracle_config)
+    };
+    let observations = if observations_info.key() == *program_id {
+        None
+    } else {
+        require!(observations_info.is_writable, ErrorCode::InvalidRoute);
+        let observations: Box<Account<'info, PoolObservations>> = Box::new(Account::try_from(observations_info)?);
+        require_keys_eq!(observations.pool, pool.key(), ErrorCode::InvalidRoute);
+        Some(observations)
+    };
+
+    let a_to_b = if input_mint == pool.mint_a {
+        true
+    } else if input_mint == pool.mint_b {
+        false
+    } else {
+        return err!(ErrorCode::InvalidRoute);
+    };
+
+    Ok(RouteHop {
+        pool,
+        vault_a,
+        vault_b,
+        mint_a: Box::new(InterfaceAccount::try_from(mint_a_info)?),
+        mint_b: Box::new(InterfaceAccount::try_from(mint_b_info)?),
+        token_program_a: Interface::try_from(token_program_a_info)?,
+        token_program_b: Interface::try_from(token_program_b_info)?,
+        oracle_config,
+        observations,
+        a_to_b,
+    })
+}
+
+/// Checks the pool's accounting against its vaults and LP mint and writes a `PoolHealth` as
+/// return data. Findings are reported rather than raised so monitors can simulate it.
+pub fn health_check(ctx: Context<HealthCheck>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let pool_key = pool.key();
+    let (vault_a, vault_b, lp_mint) = (&ctx.accounts.vault_a, &ctx.accounts.vault_b, &ctx.accounts.lp_mint);
+    let vaults_valid = vault_a.mint == pool.mint_a
+        && vault_b.mint == pool.mint_b
+        && vault_a.owner == pool_key
+        && vault_b.owner == pool_key
+        && lp_mint.mint_authority == COption::Some(pool_key);
+
+    let health = assess_pool_health(pool, vault_a.amount, vault_b.amount, lp_mint.supply, vaults_valid);
+    set_return_data(&health.try_to_vec()?);
+    Ok(())
+}
+
+fn assess_pool_health(
+    pool: &Pool,
+    balance_a: u64,
+    balance_b: u64,
+    lp_mint_supply: u64,
+    vaults_valid: bool,
+) -> PoolHealth {
+    let k_current = balance_a as u128 * balance_b as u128;
+    let lp_supply_matches = pool.lp_supply == lp_mint_supply;
+    let reserves_present = pool.lp_supply == 0 || (pool.reserve_a > 0 && pool.reserve_b > 0);
+    let reserves_backed = balance_a >= pool.reserve_a && balance_b >= pool.reserve_b;
+    let k_not_decreased = k_current >= pool.k_last;
+    PoolHealth {
+        healthy: vaults_valid && lp_supply_matches && reserves_present && reserves_backed && k_not_decreased,
+        vaults_valid,
+        lp_supply_matches,
+        reserves_present,
+        reserves_backed,
+        k_not_decreased,
+        emergency_mode: pool.emergency_mode,
+        flash_loan_active: pool.flash_loan_active,
+        reserve_a: pool.reserve_a,
+        reserve_b: pool.reserve_b,
+        balance_a,
+        balance_b,
+        lp_supply: pool.lp_supply,
+        lp_mint_supply,
+        k_last: pool.k_last,
+        k_current,
+    }
+}
+
+/// Rewrites a pool from an older layout as `POOL_VERSION`, growing the account first if the new
+/// layout is larger. The payer covers any extra rent; anyone may migrate any pool.
+pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+    let pool_info = ctx.accounts.pool.to_account_info();
+    let inputs = PoolMigrationInputs {
+        decimals_a: ctx.accounts.mint_a.decimals,
+        decimals_b: ctx.accounts.mint_b.decimals,
+        balance_a: ctx.accounts.vault_a.amount,
+        balance_b: ctx.accounts.vault_b.amount,
+        now: Clock::get()?.unix_timestamp,
+    };
+    let (pool, from_version) = upgrade_pool_data(&pool_info.try_borrow_data()?, &inputs)?;
+    require_keys_eq!(ctx.accounts.mint_a.key(), pool.mint_a, ErrorCode::InvalidVault);
+    require_keys_eq!(ctx.accounts.mint_b.key(), pool.mint_b, ErrorCode::InvalidVault);
+    require_keys_eq!(ctx.accounts.vault_a.key(), pool.vault_a, ErrorCode::InvalidVault);
+    require_keys_eq!(ctx.accounts.vault_b.key(), pool.vault_b, ErrorCode::InvalidVault);
+
+    if pool_info.data_len() < Pool::SIZE {
+        let rent_due = Rent::get()?
+            .minimum_balance(Pool::SIZE)
+            .saturating_sub(pool_info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: pool_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        pool_info.resize(Pool::SIZE)?;
+    }
+    pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+
+    emit!(PoolMigrated {
+        pool: pool_info.key(),
+        from_version,
+        to_version: pool.version,
+    });
+    Ok(())
+}
+
+/// Decodes a pool written under any earlier version and returns it upgraded to `POOL_VERSION`,
+/// along with the version it was stored as.
+///
+/// v1 is the original 316-byte layout. Everything after `last_reward_claim_ts` was zeroed reserved
+/// space, and later layouts only add fields after it, so padding a v1 account with zeros decodes
+/// every newer field as zero; `upgrade_from_v1` then fills in the ones that need real values.
+/// v2 pools already have the full size, and v3 only carved the disabled circuit breaker out of
+/// their zeroed reserved bytes.
+fn upgrade_pool_data(data: &[u8], inputs: &PoolMigrationInputs) -> Result<(Pool, u8)> {
+    require!(
+        data.len() == Pool::V1_SIZE || data.len() == Pool::SIZE,
+        ErrorCode::InvalidPoolLayout
+    );
+    let mut padded = data.to_vec();
+    padded.resize(Pool::SIZE, 0);
+    let mut pool = Pool::try_deserialize(&mut padded.as_slice())?;
+
+    let from_version = pool.version;
+    let expected_version_ok = if data.len() == Pool::V1_SIZE { from_version == 1 } else { from_version >= 2 };
+    require!(expected_version_ok, ErrorCode::InvalidPoolLayout);
+    require!(from_version < POOL_VERSION, ErrorCode::PoolAlreadyMigrated);
+    reentrancy::assert_unlocked(&pool)?;
+
+    if from_version == 1 {
+        upgrade_from_v1(&mut pool, inputs);
+    }
+    pool.version = POOL_VERSION;
+    Ok((pool, from_version))
+}
+
+fn upgrade_from_v1(pool: &mut Pool, inputs: &PoolMigrationInputs) {
+    pool.oracle_tolerance_bps = ORACLE_SLIPPAGE_BPS;
+    pool.decimals_a = inputs.decimals_a;
+    pool.decimals_b = inputs.decimals_b;
+    // Start the TWAP accumulators at migration rather than integrating from the epoch
+    pool.price_a_cumulative = 0;
+    pool.price_b_cumulative = 0;
+    pool.last_price_update_ts = inputs.now;
+    // Every v1 pool priced on x*y=k
+    pool.curve_type = CurveType::ConstantProduct;
+    pool.amp = 0;
+    pool.weight_a_bps = (BPS_DENOMINATOR / 2) as u16;
+    pool.flash_loan_active = false;
+    pool.flash_repay_a = 0;
+    pool.flash_repay_b = 0;
+    // v1 priced off vault balances, so they become the tracked reserves (and k_last)
+    pool.set_reserves(inputs.balance_a, inputs.balance_b);
+    pool.locked = false;
+    // Nobody has staked yet; reward points accrued under v1 are harvested to the first stakers
+    pool.reward_per_share = 0;
+    pool.staked_lp = 0;
+    // v1 pool PDAs are `[b"pool", mint_a, mint_b]`; keep signing with those seeds
+    pool.legacy_seeds = true;
+}
+
+/// Exact-input quote on whichever curve the pool was created with.
+pub(crate) fn pool_swap_out(pool: &Pool, amount_in: u64, reserve_in: u128, reserve_out: u128, a_to_b: bool) -> Result<u64> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => compute_swap_out(amount_in, reserve_in, reserve_out, pool.fee_bps),
+        CurveType::StableSwap => curve::compute_stable_swap_out(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool.amp,
+            pool_decimal_scales(pool, a_to_b)?,
+        ),
+        CurveType::Weighted => curve::compute_weighted_swap_out(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool_weights(pool, a_to_b),
+        ),
+        CurveType::Concentrated => err!(ErrorCode::InvalidCurve),
+    }
+}
+
+/// Exact-output quote on whichever curve the pool was created with.
+pub(crate) fn pool_swap_in(pool: &Pool, amount_out: u64, reserve_in: u128, reserve_out: u128, a_to_b: bool) -> Result<u64> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => compute_swap_in(amount_out, reserve_in, reserve_out, pool.fee_bps),
+        CurveType::StableSwap => curve::compute_stable_swap_in(
             amount_out,
-        )?;
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool.amp,
+            pool_decimal_scales(pool, a_to_b)?,
+        ),
+        CurveType::Weighted => curve::compute_weighted_swap_in(
+            amount_out,
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool_weights(pool, a_to_b),
+        ),
+        CurveType::Concentrated => err!(ErrorCode::InvalidCurve),
+    }
+}
 
-        Ok(())
-    })();
+/// `(weight_in, weight_out)` in basis points for the given direction.
+fn pool_weights(pool: &Pool, a_to_b: bool) -> (u16, u16) {
+    let weight_b = BPS_DENOMINATOR as u16 - pool.weight_a_bps;
+    if a_to_b {
+        (pool.weight_a_bps, weight_b)
+    } else {
+        (weight_b, pool.weight_a_bps)
+    }
+}
 
-    // Update pool state only if swap succeeded
-    if result.is_ok() {
-        let pool = &mut ctx.accounts.pool;
-        let fee_points = (amount_in as u128)
-            .checked_mul(REWARD_POINTS_FEE_BPS as u128)
-            .ok_or(error!(ErrorCode::ArithmeticOverflow))?
-            .checked_div(BPS_DENOMINATOR as u128)
-            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-        let effective_points = fee_points.max(MIN_REWARD_POINTS);
-        pool.reward_points = pool
-            .reward_points
-            .checked_add(effective_points)
+fn pool_decimal_scales(pool: &Pool, a_to_b: bool) -> Result<(u128, u128)> {
+    if a_to_b {
+        curve::decimal_scales(pool.decimals_a, pool.decimals_b)
+    } else {
+        curve::decimal_scales(pool.decimals_b, pool.decimals_a)
+    }
+}
+
+/// Fails when the pool's reserve ratio has drifted more than `max_deviation_bps` from the
+/// `amount_b / amount_a` ratio the depositor signed for.
+fn assert_deposit_ratio(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u128,
+    reserve_b: u128,
+    max_deviation_bps: u16,
+) -> Result<()> {
+    let pool_side = reserve_b
+        .checked_mul(amount_a as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let user_side = (amount_b as u128)
+        .checked_mul(reserve_a)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let deviation = pool_side
+        .abs_diff(user_side)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let allowed = user_side
+        .checked_mul(max_deviation_bps as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    require!(deviation <= allowed, ErrorCode::SlippageExceeded);
+    Ok(())
+}
+
+/// Returns `(swap_amount, deposited, minted)` for a single-sided deposit of `amount_in`.
+/// Bisects the swapped share so `(amount_in - swapped) / reserve_in' == out / reserve_out'`,
+/// which works for every curve since it only relies on `pool_swap_out` being monotonic.
+fn compute_single_sided_mint(
+    pool: &Pool,
+    amount_in: u64,
+    is_a: bool,
+    reserve_a: u128,
+    reserve_b: u128,
+) -> Result<(u64, u64, u64)> {
+    require!(pool.lp_supply > 0, ErrorCode::InsufficientLiquidity);
+    let (reserve_in, reserve_out) = if is_a { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+
+    let mut low = 0u64;
+    let mut high = amount_in;
+    for _ in 0..SINGLE_SIDED_SEARCH_ITERATIONS {
+        if high - low <= 1 {
+            break;
+        }
+        let mid = low + (high - low) / 2;
+        // Amounts too small to produce any output simply mean more needs to be swapped
+        let out = pool_swap_out(pool, mid, reserve_in, reserve_out, is_a).unwrap_or(0) as u128;
+        let remaining_share = ((amount_in - mid) as u128)
+            .checked_mul(reserve_out - out)
             .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-        pool.swap_count = pool
-            .swap_count
-            .checked_add(1)
+        let swapped_share = out
+            .checked_mul(reserve_in + mid as u128)
             .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        if remaining_share > swapped_share {
+            low = mid;
+        } else {
+            high = mid;
+        }
     }
 
-    result
-}
+    let swap_amount = high;
+    let swap_out = pool_swap_out(pool, swap_amount, reserve_in, reserve_out, is_a)?;
+    let remaining = amount_in - swap_amount;
+    let new_reserve_in = reserve_in
+        .checked_add(swap_amount as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let new_reserve_out = reserve_out - swap_out as u128;
 
-pub fn health_check(_ctx: Context<HealthCheck>) -> Result<()> {
-    Ok(())
+    let (used_in, minted) = if is_a {
+        let (used_a, _, minted) = compute_liquidity_mint(
+            remaining,
+            swap_out,
+            new_reserve_in,
+            new_reserve_out,
+            pool.lp_supply,
+            pool.weight_a_bps,
+        )?;
+        (used_a, minted)
+    } else {
+        let (_, used_b, minted) = compute_liquidity_mint(
+            swap_out,
+            remaining,
+            new_reserve_out,
+            new_reserve_in,
+            pool.lp_supply,
+            pool.weight_a_bps,
+        )?;
+        (used_b, minted)
+    };
+
+    let deposited = math::add_u64(swap_amount, used_in)?;
+    Ok((swap_amount, deposited, minted))
 }
 
-fn compute_liquidity_mint(
+pub(crate) fn compute_liquidity_mint(
     amount_a: u64,
     amount_b: u64,
     reserve_a: u128,
     reserve_b: u128,
     lp_supply: u64,
+    weight_a_bps: u16,
 ) -> Result<(u64, u64, u64)> {
     let mut used_a = amount_a as u128;
     let mut used_b = amount_b as u128;
     let minted: u128;
 
     if lp_supply == 0 {
-        let product = used_a
-            .checked_mul(used_b)
-            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-        let liquidity = math::integer_sqrt(product);
+        // Seed LP supply with the pool invariant: sqrt(a*b) for even weights, a^w * b^(1-w) otherwise
+        let liquidity = if weight_a_bps as u64 * 2 == BPS_DENOMINATOR {
+            let product = used_a
+                .checked_mul(used_b)
+                .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+            math::integer_sqrt(product)
+        } else {
+            math::weighted_geometric_mean(amount_a, amount_b, weight_a_bps)?
+        };
         require!(liquidity >= MIN_LIQUIDITY as u128, ErrorCode::InsufficientLiquidity);
         minted = liquidity;
     } else {
@@ -388,6 +1738,38 @@ fn compute_liquidity_mint(
     ))
 }
 
+/// Pro-rata share of both reserves redeemed by burning `lp_amount`, rounded down in the pool's favour.
+pub(crate) fn compute_withdraw_amounts(
+    lp_amount: u64,
+    reserve_a: u128,
+    reserve_b: u128,
+    lp_supply: u64,
+) -> Result<(u64, u64)> {
+    require!(lp_amount > 0, ErrorCode::ZeroAmountOut);
+    require!(lp_supply >= lp_amount, ErrorCode::InsufficientLiquidity);
+    let share = |reserve: u128| -> Result<u64> {
+        reserve
+            .checked_mul(lp_amount as u128)
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+            .checked_div(lp_supply as u128)
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+            .try_into()
+            .map_err(|_| error!(ErrorCode::ArithmeticOverflow))
+    };
+    let (amount_a, amount_b) = (share(reserve_a)?, share(reserve_b)?);
+    require!(amount_a > 0 && amount_b > 0, ErrorCode::ZeroAmountOut);
+    Ok((amount_a, amount_b))
+}
+
+/// The part of `amount_in` kept as LP fee; every curve floors the post-fee input the same way.
+fn swap_fee_amount(amount_in: u64, fee_bps: u16) -> Result<u64> {
+    let after_fee = (amount_in as u128)
+        .checked_mul((BPS_DENOMINATOR - fee_bps as u64) as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        / BPS_DENOMINATOR as u128;
+    Ok(amount_in - after_fee as u64)
+}
+
 fn compute_swap_out(amount_in: u64, reserve_in: u128, reserve_out: u128, fee_bps: u16) -> Result<u64> {
     require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
 
@@ -397,25 +1779,387 @@ fn compute_swap_out(amount_in: u64, reserve_in: u128, reserve_out: u128, fee_bps
         .checked_div(BPS_DENOMINATOR as u128)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
 
+    // out = in_after_fee * reserve_out / (reserve_in + in_after_fee), rounded down in the pool's favour
     let new_reserve_in = reserve_in
         .checked_add(amount_in_after_fee)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    let k = reserve_in
+    let amount_out = amount_in_after_fee
         .checked_mul(reserve_out)
-        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    let new_reserve_out = k
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
         .checked_div(new_reserve_in)
         .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
-    let amount_out = reserve_out
-        .checked_sub(new_reserve_out)
-        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
 
     let out_u64: u64 = amount_out.try_into().map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
     require!(out_u64 > 0, ErrorCode::ZeroAmountOut);
     Ok(out_u64)
 }
 
+/// Inverse of `compute_swap_out`: the smallest input that yields at least `amount_out`.
+/// Both divisions round up so the pool never gives away more than x*y=k allows.
+fn compute_swap_in(amount_out: u64, reserve_in: u128, reserve_out: u128, fee_bps: u16) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, ErrorCode::InsufficientLiquidity);
+    require!((fee_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidFee);
+    let amount_out = amount_out as u128;
+    require!(amount_out < reserve_out, ErrorCode::InsufficientLiquidity);
+
+    let remaining_out = reserve_out
+        .checked_sub(amount_out)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let amount_in_after_fee = reserve_in
+        .checked_mul(amount_out)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        .div_ceil(remaining_out);
+    let amount_in = amount_in_after_fee
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+        .div_ceil((BPS_DENOMINATOR - fee_bps as u64) as u128);
+
+    let in_u64: u64 = amount_in.try_into().map_err(|_| error!(ErrorCode::ArithmeticOverflow))?;
+    require!(in_u64 > 0, ErrorCode::ZeroAmount);
+    Ok(in_u64)
+}
+
 fn assert_mint_order(mint_a: &Pubkey, mint_b: &Pubkey) -> Result<()> {
     require!(mint_a < mint_b, ErrorCode::MintOrderInvalid);
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool(fee_bps: u16) -> Pool {
+        Pool {
+            mint_a: Pubkey::default(),
+            mint_b: Pubkey::default(),
+            vault_a: Pubkey::default(),
+            vault_b: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            fee_bps,
+            lp_supply: 0,
+            creator: Pubkey::default(),
+            created_at: 0,
+            bump: 0,
+            vault_a_bump: 0,
+            vault_b_bump: 0,
+            lp_mint_bump: 0,
+            emergency_mode: false,
+            max_daily_volume: 0,
+            current_daily_volume: 0,
+            last_volume_reset: 0,
+            version: POOL_VERSION,
+            features_flags: 0,
+            reward_points: 0,
+            swap_count: 0,
+            last_reward_claim_ts: 0,
+            oracle_tolerance_bps: ORACLE_SLIPPAGE_BPS,
+            decimals_a: 9,
+            decimals_b: 9,
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+            last_price_update_ts: 0,
+            curve_type: CurveType::ConstantProduct,
+            amp: 0,
+            weight_a_bps: 5_000,
+            flash_loan_active: false,
+            flash_repay_a: 0,
+            flash_repay_b: 0,
+            k_last: 0,
+            reserve_a: 0,
+            reserve_b: 0,
+            locked: false,
+            reward_per_share: 0,
+            staked_lp: 0,
+            max_price_impact_bps: 0,
+            max_window_move_bps: 0,
+            window_start_ts: 0,
+            window_start_price: 0,
+            breaker_violations: 0,
+            legacy_seeds: false,
+            _reserved: [0; 98],
+        }
+    }
+
+    const RESERVES: [(u128, u128); 4] = [
+        (1_000_000, 1_000_000),
+        (5_000_000_000, 20_000_000),
+        (123_456_789, 987_654_321_000),
+        (1_000, 1_000_000_000_000),
+    ];
+    const FEES: [u16; 4] = [0, 5, 30, 100];
+
+    #[test]
+    fn exact_out_input_delivers_requested_output() {
+        for &(reserve_in, reserve_out) in RESERVES.iter() {
+            for &fee_bps in FEES.iter() {
+                for amount_out in [1u64, 7, 999, (reserve_out / 3) as u64, (reserve_out * 9 / 10) as u64] {
+                    let amount_in = compute_swap_in(amount_out, reserve_in, reserve_out, fee_bps).unwrap();
+                    let delivered = compute_swap_out(amount_in, reserve_in, reserve_out, fee_bps).unwrap();
+                    assert!(
+                        delivered >= amount_out,
+                        "in={} out={} delivered={} reserves=({}, {}) fee={}",
+                        amount_in, amount_out, delivered, reserve_in, reserve_out, fee_bps
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exact_out_input_is_minimal() {
+        for &(reserve_in, reserve_out) in RESERVES.iter() {
+            for &fee_bps in FEES.iter() {
+                for amount_out in [7u64, 999, (reserve_out / 3) as u64] {
+                    let amount_in = compute_swap_in(amount_out, reserve_in, reserve_out, fee_bps).unwrap();
+                    if amount_in <= 1 {
+                        continue;
+                    }
+                    let short = compute_swap_out(amount_in - 1, reserve_in, reserve_out, fee_bps).unwrap_or(0);
+                    assert!(short < amount_out, "one less input still delivered {}", short);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exact_in_output_round_trips_through_exact_out() {
+        for &(reserve_in, reserve_out) in RESERVES.iter() {
+            for &fee_bps in FEES.iter() {
+                for amount_in in [1_000u64, 50_000, 10_000_000] {
+                    let Ok(amount_out) = compute_swap_out(amount_in, reserve_in, reserve_out, fee_bps) else {
+                        continue;
+                    };
+                    let required_in = compute_swap_in(amount_out, reserve_in, reserve_out, fee_bps).unwrap();
+                    assert!(required_in <= amount_in);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_sided_deposit_matches_swap_then_add() {
+        let mut pool = test_pool(30);
+        pool.lp_supply = 1_000_000_000;
+        let (reserve_a, reserve_b) = (1_000_000_000u128, 4_000_000_000u128);
+
+        let (swap_amount, deposited, minted) =
+            compute_single_sided_mint(&pool, 10_000_000, true, reserve_a, reserve_b).unwrap();
+        // Roughly half is swapped; the rest is paired with the swap output
+        assert!((4_900_000..5_100_000).contains(&swap_amount), "swapped={}", swap_amount);
+        assert!((9_999_000..=10_000_000).contains(&deposited), "deposited={}", deposited);
+
+        // Depositing the same total two-sided at the original ratio can never mint less
+        let (_, _, two_sided) =
+            compute_liquidity_mint(5_000_000, 20_000_000, reserve_a, reserve_b, pool.lp_supply, 5_000).unwrap();
+        assert!(minted < two_sided && minted * 1_000 >= two_sided * 990, "minted={} two_sided={}", minted, two_sided);
+    }
+
+    #[test]
+    fn single_sided_deposit_requires_seeded_pool() {
+        let pool = test_pool(30);
+        assert!(compute_single_sided_mint(&pool, 10_000, true, 0, 0).is_err());
+    }
+
+    #[test]
+    fn exact_out_rejects_draining_the_pool() {
+        assert!(compute_swap_in(1_000, 1_000, 1_000, 30).is_err());
+        assert!(compute_swap_in(2_000, 1_000, 1_000, 30).is_err());
+        assert!(compute_swap_in(1, 0, 1_000, 30).is_err());
+        assert!(compute_swap_in(1, 1_000, 1_000, 10_000).is_err());
+    }
+
+    #[test]
+    fn lp_token_name_is_derived_from_pair() {
+        assert_eq!(lp_token_name("SOL", "USDC").unwrap(), "AEGIS-LP SOL/USDC");
+        assert!(lp_token_name("", "USDC").is_err());
+        assert!(lp_token_name("SOL", "US/DC").is_err());
+        assert!(lp_token_name("SOL USD", "USDC").is_err());
+        // Names longer than Metaplex's 32-byte limit are rejected rather than truncated
+        assert!(lp_token_name("ABCDEFGHIJK", "ABCDEFGHIJK").is_ok());
+        assert!(lp_token_name("ABCDEFGHIJKL", "ABCDEFGHIJK").is_err());
+    }
+
+    #[test]
+    fn swap_fee_matches_floored_post_fee_input() {
+        assert_eq!(swap_fee_amount(10_000, 30).unwrap(), 30);
+        assert_eq!(swap_fee_amount(1, 30).unwrap(), 1);
+        assert_eq!(swap_fee_amount(0, 30).unwrap(), 0);
+    }
+
+    #[test]
+    fn swaps_move_tracked_reserves_and_k() {
+        let mut pool = test_pool(30);
+        pool.set_reserves(1_000, 4_000);
+        apply_swap_to_reserves(&mut pool, true, 100, 360).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_100, 3_640));
+        assert_eq!(pool.k_last, 1_100 * 3_640);
+        apply_swap_to_reserves(&mut pool, false, 40, 12).unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_088, 3_680));
+        assert!(apply_swap_to_reserves(&mut pool, true, 1, 3_681).is_err());
+    }
+
+    #[test]
+    fn withdraw_amounts_are_pro_rata_rounded_down() {
+        assert_eq!(compute_withdraw_amounts(250, 1_000, 4_001, 1_000).unwrap(), (250, 1_000));
+        assert_eq!(compute_withdraw_amounts(1_000, 1_000, 4_001, 1_000).unwrap(), (1_000, 4_001));
+        assert!(compute_withdraw_amounts(1, 999, 4_000, 1_000).is_err());
+        assert!(compute_withdraw_amounts(1_001, 1_000, 4_000, 1_000).is_err());
+    }
+
+    #[test]
+    fn health_flags_each_broken_invariant() {
+        let mut pool = test_pool(30);
+        pool.lp_supply = 1_000;
+        pool.set_reserves(2_000, 500);
+
+        // A donation sits above the tracked reserves without affecting health
+        let health = assess_pool_health(&pool, 2_000, 600, 1_000, true);
+        assert!(health.healthy);
+        assert_eq!(health.k_current, 1_200_000);
+
+        assert!(!assess_pool_health(&pool, 2_000, 600, 999, true).lp_supply_matches);
+        let drained = assess_pool_health(&pool, 2_000, 499, 1_000, true);
+        assert!(!drained.k_not_decreased && !drained.reserves_backed);
+        assert!(!assess_pool_health(&pool, 2_000, 600, 1_000, false).healthy);
+
+        pool.set_reserves(0, 500);
+        assert!(!assess_pool_health(&pool, 2_000, 600, 1_000, true).reserves_present);
+
+        pool.lp_supply = 0;
+        pool.set_reserves(0, 0);
+        assert!(assess_pool_health(&pool, 0, 0, 0, true).healthy);
+    }
+
+    fn serialized(pool: &Pool) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Pool::SIZE);
+        pool.try_serialize(&mut data).unwrap();
+        data.resize(Pool::SIZE, 0);
+        data
+    }
+
+    const MIGRATION_INPUTS: PoolMigrationInputs = PoolMigrationInputs {
+        decimals_a: 6,
+        decimals_b: 9,
+        balance_a: 1_000_000,
+        balance_b: 2_000_000,
+        now: 1_800_000_000,
+    };
+
+    /// A pool as the original `initialize_pool` wrote it after some trading, field by field in the
+    /// v1 order with its 32 zeroed reserved bytes.
+    fn v1_pool_fixture() -> Vec<u8> {
+        let mut data = Pool::DISCRIMINATOR.to_vec();
+        for key in 1..=5u8 {
+            data.extend_from_slice(&[key; 32]); // mint_a, mint_b, vault_a, vault_b, lp_mint
+        }
+        data.extend_from_slice(&30u16.to_le_bytes()); // fee_bps
+        data.extend_from_slice(&1_414_213u64.to_le_bytes()); // lp_supply
+        data.extend_from_slice(&[6; 32]); // creator
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // created_at
+        data.extend_from_slice(&[254, 253, 252, 251]); // bumps
+        data.push(0); // emergency_mode
+        data.extend_from_slice(&0u64.to_le_bytes()); // max_daily_volume
+        data.extend_from_slice(&0u64.to_le_bytes()); // current_daily_volume
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // last_volume_reset
+        data.push(1); // version
+        data.extend_from_slice(&0u32.to_le_bytes()); // features_flags
+        data.extend_from_slice(&4_200u128.to_le_bytes()); // reward_points
+        data.extend_from_slice(&17u64.to_le_bytes()); // swap_count
+        data.extend_from_slice(&1_700_000_500i64.to_le_bytes()); // last_reward_claim_ts
+        data.extend_from_slice(&[0; 32]); // reserved
+        assert_eq!(data.len(), Pool::V1_SIZE);
+        data
+    }
+
+    #[test]
+    fn v1_pools_migrate_to_current_layout() {
+        let v1 = v1_pool_fixture();
+        assert!(Pool::try_deserialize(&mut v1.as_slice()).is_err());
+
+        let (pool, from_version) = upgrade_pool_data(&v1, &MIGRATION_INPUTS).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(pool.version, POOL_VERSION);
+
+        // Every v1 field survives in place.
+        assert_eq!(
+            [pool.mint_a, pool.mint_b, pool.vault_a, pool.vault_b, pool.lp_mint, pool.creator],
+            [1, 2, 3, 4, 5, 6].map(|byte| Pubkey::new_from_array([byte; 32]))
+        );
+        assert_eq!(pool.fee_bps, 30);
+        assert_eq!(pool.lp_supply, 1_414_213);
+        assert_eq!(pool.created_at, 1_700_000_000);
+        assert_eq!(
+            [pool.bump, pool.vault_a_bump, pool.vault_b_bump, pool.lp_mint_bump],
+            [254, 253, 252, 251]
+        );
+        assert_eq!(pool.last_volume_reset, 1_700_000_000);
+        assert_eq!(pool.reward_points, 4_200);
+        assert_eq!(pool.swap_count, 17);
+        assert_eq!(pool.last_reward_claim_ts, 1_700_000_500);
+
+        // Fields v1 never had are initialised rather than left as decoded padding.
+        assert_eq!(pool.oracle_tolerance_bps, ORACLE_SLIPPAGE_BPS);
+        assert_eq!((pool.decimals_a, pool.decimals_b), (6, 9));
+        assert_eq!((pool.price_a_cumulative, pool.price_b_cumulative), (0, 0));
+        assert_eq!(pool.last_price_update_ts, MIGRATION_INPUTS.now);
+        assert_eq!(pool.curve_type, CurveType::ConstantProduct);
+        assert_eq!(pool.amp, 0);
+        assert_eq!(pool.weight_a_bps, 5_000);
+        assert!(!pool.flash_loan_active);
+        assert_eq!((pool.flash_repay_a, pool.flash_repay_b), (0, 0));
+        assert_eq!((pool.reserve_a, pool.reserve_b), (1_000_000, 2_000_000));
+        assert_eq!(pool.k_last, 2_000_000_000_000);
+        assert!(!pool.locked);
+        assert_eq!((pool.reward_per_share, pool.staked_lp), (0, 0));
+        assert!(pool.legacy_seeds);
+        assert!(pool.fee_seed().is_empty());
+        assert!(pool._reserved.iter().all(|byte| *byte == 0));
+
+        let err = upgrade_pool_data(&serialized(&pool), &MIGRATION_INPUTS).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::PoolAlreadyMigrated));
+        let err = upgrade_pool_data(&v1[..Pool::V1_SIZE - 1], &MIGRATION_INPUTS).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::InvalidPoolLayout));
+        let mut mislabelled = v1.clone();
+        mislabelled[Pool::V1_SIZE - 32 - 8 - 8 - 16 - 4 - 1] = 2;
+        let err = upgrade_pool_data(&mislabelled, &MIGRATION_INPUTS).unwrap_err();
+        assert_eq!(err, error!(ErrorCode::InvalidPoolLayout));
+    }
+
+    #[test]
+    fn v2_pools_only_change_version() {
+        let mut pool = test_pool(30);
+        pool.version = 2;
+        pool.set_reserves(1_000_000, 2_000_000);
+        pool.lp_supply = 1_414_213;
+
+        let (migrated, from_version) = upgrade_pool_data(&serialized(&pool), &MIGRATION_INPUTS).unwrap();
+        assert_eq!(from_version, 2);
+        pool.version = POOL_VERSION;
+        assert_eq!(serialized(&migrated), serialized(&pool));
+    }
+
+    #[test]
+    fn legacy_pools_sign_with_their_original_seeds() {
+        let mut pool = test_pool(30);
+        pool.mint_a = Pubkey::new_unique();
+        pool.mint_b = Pubkey::new_unique();
+        let signer_address = |pool: &Pool| {
+            let (_bump, signer_seeds) = seeds::pool_signer_seeds(pool);
+            let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+            Pubkey::create_program_address(&signer_seeds_slice, &crate::ID).unwrap()
+        };
+
+        let (tiered, bump) = Pubkey::find_program_address(
+            &[b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), &30u16.to_le_bytes()],
+            &crate::ID,
+        );
+        pool.bump = bump;
+        assert_eq!(signer_address(&pool), tiered);
+
+        let (legacy, bump) =
+            Pubkey::find_program_address(&[b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref()], &crate::ID);
+        pool.bump = bump;
+        pool.legacy_seeds = true;
+        assert_eq!(signer_address(&pool), legacy);
+    }
+}
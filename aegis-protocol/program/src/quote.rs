@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    circuit_breaker,
+    pool::{compute_liquidity_mint, compute_withdraw_amounts, pool_swap_in, pool_swap_out, post_swap_reserves},
+    state::{AddLiquidityQuote, Pool, RemoveLiquidityQuote, SwapQuote},
+    token_utils, validation,
+};
+
+/// Read-only view of a pool for the quote instructions; meant to be run with `simulateTransaction`.
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(address = pool.mint_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+}
+
+/// Mirrors `swap`: the input is priced net of its transfer fee and `amount_out` is what the
+/// destination account would actually receive. Fails where `swap` would.
+pub fn quote_swap(ctx: Context<Quote>, amount_in: u64, a_to_b: bool) -> Result<()> {
+    validation::assert_positive(amount_in)?;
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+
+    let (input_mint, output_mint, reserve_in, reserve_out) = if a_to_b {
+        (&accounts.mint_a, &accounts.mint_b, accounts.pool.reserve_a, accounts.pool.reserve_b)
+    } else {
+        (&accounts.mint_b, &accounts.mint_a, accounts.pool.reserve_b, accounts.pool.reserve_a)
+    };
+    let received_in = token_utils::net_of_transfer_fee(input_mint, amount_in)?;
+    let pool_out = pool_swap_out(&accounts.pool, received_in, reserve_in as u128, reserve_out as u128, a_to_b)?;
+    assert_swap_allowed(&accounts.pool, a_to_b, received_in, pool_out)?;
+    let amount_out = token_utils::net_of_transfer_fee(output_mint, pool_out)?;
+
+    set_return_data(&SwapQuote { amount_in, amount_out }.try_to_vec()?);
+    Ok(())
+}
+
+/// Mirrors `swap_exact_out`: `amount_in` is what the source must send for the destination to net
+/// `amount_out` after transfer fees on both legs. Fails where `swap_exact_out` would.
+pub fn quote_swap_exact_out(ctx: Context<Quote>, amount_out: u64, a_to_b: bool) -> Result<()> {
+    validation::assert_positive(amount_out)?;
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+
+    let (input_mint, output_mint, reserve_in, reserve_out) = if a_to_b {
+        (&accounts.mint_a, &accounts.mint_b, accounts.pool.reserve_a, accounts.pool.reserve_b)
+    } else {
+        (&accounts.mint_b, &accounts.mint_a, accounts.pool.reserve_b, accounts.pool.reserve_a)
+    };
+    let gross_out = token_utils::gross_up_for_transfer_fee(output_mint, amount_out)?;
+    let net_in = pool_swap_in(&accounts.pool, gross_out, reserve_in as u128, reserve_out as u128, a_to_b)?;
+    assert_swap_allowed(&accounts.pool, a_to_b, net_in, gross_out)?;
+    let amount_in = token_utils::gross_up_for_transfer_fee(input_mint, net_in)?;
+
+    set_return_data(&SwapQuote { amount_in, amount_out }.try_to_vec()?);
+    Ok(())
+}
+
+/// Rejects a quote for a trade the pool would refuse: emergency mode, or a move that trips the
+/// circuit breaker. The breaker records strikes, so it runs against a copy of the pool.
+fn assert_swap_allowed(pool: &Pool, a_to_b: bool, amount_in: u64, amount_out: u64) -> Result<()> {
+    validation::assert_not_emergency(pool)?;
+    let (post_a, post_b) = post_swap_reserves(pool, a_to_b, amount_in, amount_out)?;
+    circuit_breaker::check_swap(&mut pool.clone(), post_a, post_b, Clock::get()?.unix_timestamp)?;
+    Ok(())
+}
+
+/// Mirrors `add_liquidity`: LP is minted against what the vaults would receive after transfer fees.
+pub fn quote_add_liquidity(ctx: Context<Quote>, amount_a: u64, amount_b: u64) -> Result<()> {
+    validation::assert_positive(amount_a)?;
+    validation::assert_positive(amount_b)?;
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+
+    let pool = &accounts.pool;
+    let (reserve_a, reserve_b) = (pool.reserve_a as u128, pool.reserve_b as u128);
+    let (used_a, used_b, _) =
+        compute_liquidity_mint(amount_a, amount_b, reserve_a, reserve_b, pool.lp_supply, pool.weight_a_bps)?;
+    let received_a = token_utils::net_of_transfer_fee(&accounts.mint_a, used_a)?;
+    let received_b = token_utils::net_of_transfer_fee(&accounts.mint_b, used_b)?;
+    let (_, _, lp_amount) =
+        compute_liquidity_mint(received_a, received_b, reserve_a, reserve_b, pool.lp_supply, pool.weight_a_bps)?;
+
+    let quote = AddLiquidityQuote {
+        amount_a: used_a,
+        amount_b: used_b,
+        lp_amount,
+    };
+    set_return_data(&quote.try_to_vec()?);
+    Ok(())
+}
+
+/// Mirrors `remove_liquidity`, net of any transfer fee on the way out.
+pub fn quote_remove_liquidity(ctx: Context<Quote>, lp_amount: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+
+    let (withdraw_a, withdraw_b) = compute_withdraw_amounts(
+        lp_amount,
+        accounts.pool.reserve_a as u128,
+        accounts.pool.reserve_b as u128,
+        accounts.pool.lp_supply,
+    )?;
+    let quote = RemoveLiquidityQuote {
+        lp_amount,
+        amount_a: token_utils::net_of_transfer_fee(&accounts.mint_a, withdraw_a)?,
+        amount_b: token_utils::net_of_transfer_fee(&accounts.mint_b, withdraw_b)?,
+    };
+    set_return_data(&quote.try_to_vec()?);
+    Ok(())
+}
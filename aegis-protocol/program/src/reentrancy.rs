@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::ErrorCode,
+    state::{ClPool, EmissionVault, Pool},
+};
+
+/// Accounts carrying a persistent lock bit that rejects re-entry through CPIs.
+pub trait Lockable {
+    fn lock_flag(&mut self) -> &mut bool;
+    fn is_locked(&self) -> bool;
+}
+
+impl Lockable for Pool {
+    fn lock_flag(&mut self) -> &mut bool {
+        &mut self.locked
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Lockable for ClPool {
+    fn lock_flag(&mut self) -> &mut bool {
+        &mut self.locked
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Lockable for EmissionVault {
+    fn lock_flag(&mut self) -> &mut bool {
+        &mut self.locked
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+/// Takes the lock and writes it through to account data before any CPI, so a nested call into
+/// this program deserializes the locked state and is rejected. A failed instruction reverts the
+/// write, so the lock can only outlive an instruction that forgot to call `unlock`.
+pub fn lock<'info, T>(account: &mut Account<'info, T>, program_id: &Pubkey) -> Result<()>
+where
+    T: Lockable + AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    let flag = account.lock_flag();
+    require!(!*flag, ErrorCode::ReentrancyDetected);
+    *flag = true;
+    account.exit(program_id)
+}
+
+/// Releases the lock; Anchor persists the cleared bit when the instruction exits.
+pub fn unlock<'info, T>(account: &mut Account<'info, T>)
+where
+    T: Lockable + AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    *account.lock_flag() = false;
+}
+
+/// For instructions that mutate a lockable account without making CPIs themselves.
+pub fn assert_unlocked<T: Lockable>(account: &T) -> Result<()> {
+    require!(!account.is_locked(), ErrorCode::ReentrancyDetected);
+    Ok(())
+}
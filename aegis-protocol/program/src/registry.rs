@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::ErrorCode,
+    state::{PoolRegistry, PoolRegistryEntry, PoolRegistryPage},
+};
+
+#[derive(Accounts)]
+pub struct InitializePoolRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = PoolRegistry::SIZE,
+        seeds = [b"pool_registry"],
+        bump
+    )]
+    pub registry: Account<'info, PoolRegistry>,
+    #[account(
+        init,
+        payer = payer,
+        space = PoolRegistryPage::SIZE,
+        seeds = [b"pool_registry_page", 0u32.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub first_page: Box<Account<'info, PoolRegistryPage>>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Opens the next page once the current one is full; permissionless so pool creation never stalls.
+#[derive(Accounts)]
+pub struct AddRegistryPage<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, seeds = [b"pool_registry"], bump = registry.bump)]
+    pub registry: Account<'info, PoolRegistry>,
+    #[account(
+        seeds = [b"pool_registry_page", last_page.page_index.to_le_bytes().as_ref()],
+        bump = last_page.bump,
+        constraint = last_page.page_index + 1 == registry.page_count @ ErrorCode::InvalidRegistryPage
+    )]
+    pub last_page: Box<Account<'info, PoolRegistryPage>>,
+    #[account(
+        init,
+        payer = payer,
+        space = PoolRegistryPage::SIZE,
+        seeds = [b"pool_registry_page", registry.page_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub new_page: Box<Account<'info, PoolRegistryPage>>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_pool_registry(ctx: Context<InitializePoolRegistry>) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+    registry.bump = ctx.bumps.registry;
+    registry.page_count = 1;
+    registry.pool_count = 0;
+
+    let page = &mut ctx.accounts.first_page;
+    page.page_index = 0;
+    page.bump = ctx.bumps.first_page;
+    page.entries = Vec::new();
+    Ok(())
+}
+
+pub fn add_registry_page(ctx: Context<AddRegistryPage>) -> Result<()> {
+    require!(ctx.accounts.last_page.is_full(), ErrorCode::InvalidRegistryPage);
+
+    let registry = &mut ctx.accounts.registry;
+    let page = &mut ctx.accounts.new_page;
+    page.page_index = registry.page_count;
+    page.bump = ctx.bumps.new_page;
+    page.entries = Vec::new();
+    registry.page_count = registry
+        .page_count
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    Ok(())
+}
+
+/// Records a newly created pool on the registry's last page.
+pub fn register_pool(
+    registry: &mut PoolRegistry,
+    page: &mut PoolRegistryPage,
+    entry: PoolRegistryEntry,
+) -> Result<()> {
+    require!(page.page_index + 1 == registry.page_count, ErrorCode::InvalidRegistryPage);
+    require!(!page.is_full(), ErrorCode::RegistryPageFull);
+
+    page.entries.push(entry);
+    registry.pool_count = registry
+        .pool_count
+        .checked_add(1)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{reentrancy, seeds, state::Pool, token_utils, twap, validation};
+
+/// Reserves were reset to the vault balances by `sync`
+#[event]
+pub struct Synced {
+    pub pool: Pubkey,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+}
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    pub caller: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(mut, address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.mint_a, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pool.mint_b, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = mint_a)]
+    pub destination_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = mint_b)]
+    pub destination_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(address = pool.vault_a)]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(address = pool.vault_b)]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Sends whatever the vaults hold above the tracked reserves to the caller's chosen accounts.
+/// Permissionless, as in Uniswap v2: donations never become part of the pool's price.
+pub fn skim(ctx: Context<Skim>) -> Result<()> {
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+    reentrancy::lock(&mut accounts.pool, ctx.program_id)?;
+
+    let excess_a = accounts.vault_a.amount.saturating_sub(accounts.pool.reserve_a);
+    let excess_b = accounts.vault_b.amount.saturating_sub(accounts.pool.reserve_b);
+    let (_bump_bytes, signer_seeds) = seeds::pool_signer_seeds(&accounts.pool);
+    let signer_seeds_slice: Vec<&[u8]> = signer_seeds.iter().map(|s| s.as_slice()).collect();
+    if excess_a > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_a.to_account_info(),
+            accounts.vault_a.to_account_info(),
+            &accounts.mint_a,
+            accounts.destination_a.to_account_info(),
+            accounts.pool.to_account_info(),
+            &[&signer_seeds_slice],
+            excess_a,
+        )?;
+    }
+    if excess_b > 0 {
+        token_utils::transfer_checked(
+            accounts.token_program_b.to_account_info(),
+            accounts.vault_b.to_account_info(),
+            &accounts.mint_b,
+            accounts.destination_b.to_account_info(),
+            accounts.pool.to_account_info(),
+            &[&signer_seeds_slice],
+            excess_b,
+        )?;
+    }
+    reentrancy::unlock(&mut accounts.pool);
+    Ok(())
+}
+
+/// Adopts the current vault balances as the pool's reserves.
+pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+    let accounts = ctx.accounts;
+    validation::assert_no_flash_loan(&accounts.pool)?;
+    reentrancy::assert_unlocked(&*accounts.pool)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut accounts.pool;
+    let (reserve_a, reserve_b) = (pool.reserve_a, pool.reserve_b);
+    twap::update_cumulative_prices(pool, reserve_a, reserve_b, now)?;
+    pool.set_reserves(accounts.vault_a.amount, accounts.vault_b.amount);
+
+    emit!(Synced {
+        pool: pool.key(),
+        reserve_a: pool.reserve_a,
+        reserve_b: pool.reserve_b,
+    });
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::state::Pool;
+use crate::state::{ClPool, Pool};
 
 pub fn vault_signer_seeds(owner: &Pubkey, bump: u8) -> (u8, Vec<Vec<u8>>) {
     let seeds = vec![
@@ -15,6 +15,18 @@ pub fn pool_signer_seeds(pool: &Pool) -> (u8, Vec<Vec<u8>>) {
         b"pool".to_vec(),
         pool.mint_a.to_bytes().to_vec(),
         pool.mint_b.to_bytes().to_vec(),
+        pool.fee_seed(),
+        vec![pool.bump],
+    ];
+    (pool.bump, seeds)
+}
+
+pub fn cl_pool_signer_seeds(pool: &ClPool) -> (u8, Vec<Vec<u8>>) {
+    let seeds = vec![
+        b"cl_pool".to_vec(),
+        pool.mint_a.to_bytes().to_vec(),
+        pool.mint_b.to_bytes().to_vec(),
+        pool.fee_bps.to_le_bytes().to_vec(),
         vec![pool.bump],
     ];
     (pool.bump, seeds)
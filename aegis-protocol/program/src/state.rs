@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{MAX_FEE_TIERS, TICK_ARRAY_SIZE};
+
 #[account]
 #[derive(Debug)]
 pub struct Pool {
@@ -9,7 +11,7 @@ pub struct Pool {
     pub vault_a: Pubkey,
     pub vault_b: Pubkey,
     pub lp_mint: Pubkey,
-    pub fee_bps: u16,
+    pub fee_bps: u16, // part of the pool PDA seed, fixed at creation
     pub lp_supply: u64,
     pub creator: Pubkey,
     pub created_at: i64,
@@ -33,8 +35,52 @@ pub struct Pool {
     pub swap_count: u64,
     pub last_reward_claim_ts: i64,
 
-    // Future expansion
-    pub _reserved: [u8; 32],
+    // Oracle guard
+    pub oracle_tolerance_bps: u16,
+    pub decimals_a: u8,
+    pub decimals_b: u8,
+
+    // TWAP accumulators (Q64.64 price * seconds)
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+    pub last_price_update_ts: i64,
+
+    // Pricing curve
+    pub curve_type: CurveType,
+    pub amp: u64,
+    pub weight_a_bps: u16,
+
+    // Flash loan in progress: vault balances `flash_repay` must restore
+    pub flash_loan_active: bool,
+    pub flash_repay_a: u64,
+    pub flash_repay_b: u64,
+
+    // Product of the reserves left by the last pool operation
+    pub k_last: u128,
+
+    // Reserves the pool prices against; vault balances above them are donations `skim` can sweep
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+
+    // Set while an instruction that makes CPIs is running
+    pub locked: bool,
+
+    // LP staking: AEGIS owed per staked LP token (Q64.64) and total LP staked
+    pub reward_per_share: u128,
+    pub staked_lp: u64,
+
+    // Circuit breaker (v3): zero limits are disabled; the window opens at the first swap after expiry
+    pub max_price_impact_bps: u16,
+    pub max_window_move_bps: u16,
+    pub window_start_ts: i64,
+    pub window_start_price: u128,
+    pub breaker_violations: u8,
+
+    // Set on pools migrated from v1, whose PDA was derived without the fee tier seed
+    pub legacy_seeds: bool,
+
+    // Future expansion; new fields are carved from here before the account has to grow again
+    pub _reserved: [u8; 98],
 }
 
 impl Pool {
@@ -51,7 +97,274 @@ impl Pool {
         + 16 // reward_points
         + 8 // swap_count
         + 8 // last_reward_claim_ts
-        + 32; // reserved
+        + 2 + 1 + 1 // oracle tolerance + mint decimals
+        + 16 + 16 + 8 // price accumulators + last update
+        + 1 + 8 + 2 // curve type + amplification + weight of token A
+        + 1 + 8 + 8 // flash loan flag + repayment targets
+        + 16 // k_last
+        + 8 + 8 // tracked reserves
+        + 1 // reentrancy lock
+        + 16 + 8 // reward accumulator + staked LP
+        + 2 + 2 // circuit breaker limits
+        + 8 + 16 + 1 // breaker window start + reference price + violations
+        + 1 // legacy seed scheme
+        + 98; // reserved
+
+    /// Size of the original layout: the fields up to `last_reward_claim_ts` plus 32 reserved bytes.
+    pub const V1_SIZE: usize = 316;
+
+    /// Fee tier seed of the pool PDA; empty for legacy pools, whose address has no such seed.
+    pub fn fee_seed(&self) -> Vec<u8> {
+        if self.legacy_seeds {
+            Vec::new()
+        } else {
+            self.fee_bps.to_le_bytes().to_vec()
+        }
+    }
+
+    pub fn set_reserves(&mut self, reserve_a: u64, reserve_b: u64) {
+        self.reserve_a = reserve_a;
+        self.reserve_b = reserve_b;
+        self.k_last = reserve_a as u128 * reserve_b as u128;
+    }
+}
+
+/// Diagnostics written as return data by `health_check`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolHealth {
+    pub healthy: bool,
+    pub vaults_valid: bool,
+    pub lp_supply_matches: bool,
+    pub reserves_present: bool,
+    pub reserves_backed: bool,
+    pub k_not_decreased: bool,
+    pub emergency_mode: bool,
+    pub flash_loan_active: bool,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub balance_a: u64,
+    pub balance_b: u64,
+    pub lp_supply: u64,
+    pub lp_mint_supply: u64,
+    pub k_last: u128,
+    pub k_current: u128,
+}
+
+/// Return data of `quote_swap` and `quote_swap_exact_out`: what the swap would take and deliver right now
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Return data of `quote_add_liquidity`: the amounts `add_liquidity` would pull and the LP it would mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddLiquidityQuote {
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_amount: u64,
+}
+
+/// Return data of `quote_remove_liquidity`: what the user would receive for burning `lp_amount`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RemoveLiquidityQuote {
+    pub lp_amount: u64,
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    ConstantProduct,
+    StableSwap,
+    Weighted,
+    /// Only used to tag `ClPool`s in the registry; constant-function pools cannot use it
+    Concentrated,
+}
+
+/// Number of slots in a pool's observation ring buffer
+pub const OBSERVATION_CAPACITY: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price_a_cumulative: u128,
+    pub price_b_cumulative: u128,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct PoolObservations {
+    pub pool: Pubkey,
+    pub index: u16,
+    pub bump: u8,
+    pub observations: [Observation; OBSERVATION_CAPACITY],
+}
+
+impl PoolObservations {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 // pool
+        + 2 // index
+        + 1 // bump
+        + (8 + 16 + 16) * OBSERVATION_CAPACITY; // observations
+}
+
+/// Concentrated-liquidity pool: LPs provide liquidity over tick ranges instead of the whole curve.
+/// Prices are Q64.64 square roots of token B per token A, and `tick_current` is the greatest tick
+/// at or below the current price.
+#[account]
+#[derive(Debug)]
+pub struct ClPool {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub fee_bps: u16, // part of the pool PDA seed, fixed at creation
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub fee_growth_global_a_x64: u128,
+    pub fee_growth_global_b_x64: u128,
+    pub creator: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+    pub emergency_mode: bool,
+    pub locked: bool,
+    pub _reserved: [u8; 30],
+}
+
+impl ClPool {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 * 4 // mints + vaults
+        + 2 + 2 // fee + tick spacing
+        + 16 + 16 + 4 // liquidity + price + current tick
+        + 16 + 16 // fee growth
+        + 32 + 8 + 1 // creator + created_at + bump
+        + 1 + 1 // emergency mode + lock
+        + 30; // reserved
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tick {
+    pub initialized: bool,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_a_x64: u128,
+    pub fee_growth_outside_b_x64: u128,
+}
+
+/// `TICK_ARRAY_SIZE` consecutive initializable ticks starting at `start_tick_index`.
+#[account]
+#[derive(Debug)]
+pub struct TickArray {
+    pub cl_pool: Pubkey,
+    pub start_tick_index: i32,
+    pub bump: u8,
+    pub ticks: [Tick; TICK_ARRAY_SIZE],
+}
+
+impl TickArray {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 // cl_pool
+        + 4 // start_tick_index
+        + 1 // bump
+        + (1 + 16 + 16 + 16 + 16) * TICK_ARRAY_SIZE; // ticks
+
+    /// Index into `ticks` for `tick_index`, if this array covers it.
+    pub fn tick_offset(&self, tick_index: i32, tick_spacing: u16) -> Option<usize> {
+        let spacing = tick_spacing as i32;
+        let offset = (tick_index - self.start_tick_index).div_euclid(spacing);
+        (0..TICK_ARRAY_SIZE as i32).contains(&offset).then_some(offset as usize)
+    }
+}
+
+#[account]
+#[derive(Debug)]
+pub struct ClPosition {
+    pub cl_pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_a_last_x64: u128,
+    pub fee_growth_inside_b_last_x64: u128,
+    pub tokens_owed_a: u64,
+    pub tokens_owed_b: u64,
+    pub bump: u8,
+}
+
+impl ClPosition {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 + 32 // cl_pool + owner
+        + 4 + 4 // tick range
+        + 16 // liquidity
+        + 16 + 16 // fee growth checkpoints
+        + 8 + 8 // tokens owed
+        + 1; // bump
+}
+
+/// Admin-managed list of fee tiers pools may be created with.
+#[account]
+#[derive(Debug)]
+pub struct FeeTiers {
+    pub admin: Pubkey,
+    pub bump: u8,
+    pub tiers: Vec<u16>,
+}
+
+impl FeeTiers {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 // admin
+        + 1 // bump
+        + 4 + 2 * MAX_FEE_TIERS; // tiers
+}
+
+/// Pools recorded per registry page
+pub const REGISTRY_PAGE_CAPACITY: usize = 64;
+
+/// Global index of registry pages, so every pool can be enumerated on-chain.
+#[account]
+#[derive(Debug)]
+pub struct PoolRegistry {
+    pub bump: u8,
+    pub page_count: u32,
+    pub pool_count: u64,
+}
+
+impl PoolRegistry {
+    pub const SIZE: usize = 8 // discriminator
+        + 1 // bump
+        + 4 // page_count
+        + 8; // pool_count
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolRegistryEntry {
+    pub pool: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub fee_bps: u16,
+    pub curve_type: CurveType,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct PoolRegistryPage {
+    pub page_index: u32,
+    pub bump: u8,
+    pub entries: Vec<PoolRegistryEntry>,
+}
+
+impl PoolRegistryPage {
+    pub const SIZE: usize = 8 // discriminator
+        + 4 // page_index
+        + 1 // bump
+        + 4 + (32 * 3 + 2 + 1) * REGISTRY_PAGE_CAPACITY; // entries
+
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= REGISTRY_PAGE_CAPACITY
+    }
 }
 
 #[account]
@@ -67,7 +380,8 @@ pub struct EmissionVault {
     pub lm_vault: Pubkey,
     pub team_vault: Pubkey,
     pub ecosystem_vault: Pubkey,
-    pub _reserved: [u8; 16],
+    pub locked: bool, // reentrancy lock, held across CPIs; taken from the reserved bytes
+    pub _reserved: [u8; 15],
 }
 
 impl EmissionVault {
@@ -78,7 +392,8 @@ impl EmissionVault {
         + 16 // total_emitted
         + 8 // weekly_amount
         + 32 * 5 // pubkeys
-        + 16; // reserved
+        + 1 // locked
+        + 15; // reserved
 }
 
 #[account]
@@ -211,4 +526,85 @@ pub enum ActionType {
     Transfer,
     Withdraw,
     LargeTransfer,
-}
\ No newline at end of file
+}
+
+/// Escrowed order to sell `amount_in` of `input_mint` through `pool` for at least `min_amount_out`.
+/// Funds sit in the pool's per-mint order escrow until filled or cancelled.
+#[account]
+#[derive(Debug)]
+pub struct LimitOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub input_mint: Pubkey,
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 + 32 // pool + owner
+        + 8 // order_id
+        + 32 + 1 // input mint + direction
+        + 8 + 8 // amount_in + min_amount_out
+        + 8 + 8 // created_at + expires_at
+        + 1; // bump
+}
+
+/// Recurring order selling `slice_amount` of `input_mint` every `interval_seconds` until the
+/// escrowed `remaining` runs out. Each slice must beat the oracle price by `max_slippage_bps`.
+#[account]
+#[derive(Debug)]
+pub struct DcaOrder {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub input_mint: Pubkey,
+    pub a_to_b: bool,
+    pub remaining: u64,
+    pub slice_amount: u64,
+    pub interval_seconds: i64,
+    pub max_slippage_bps: u16,
+    pub last_executed_at: i64,
+    pub slices_executed: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl DcaOrder {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 + 32 // pool + owner
+        + 8 // order_id
+        + 32 + 1 // input mint + direction
+        + 8 + 8 // remaining + slice_amount
+        + 8 + 2 // interval + slippage
+        + 8 + 4 // last execution + slice count
+        + 8 // created_at
+        + 1; // bump
+}
+
+/// An owner's staked LP in one pool. `reward_debt` is the share of `Pool::reward_per_share`
+/// already settled into `pending_rewards` for the current stake.
+#[account]
+#[derive(Debug)]
+pub struct LpPosition {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub staked: u64,
+    pub reward_debt: u128,
+    pub pending_rewards: u64,
+    pub bump: u8,
+}
+
+impl LpPosition {
+    pub const SIZE: usize = 8 // discriminator
+        + 32 + 32 // pool + owner
+        + 8 // staked
+        + 16 // reward_debt
+        + 8 // pending_rewards
+        + 1; // bump
+}
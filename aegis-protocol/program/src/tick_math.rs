@@ -0,0 +1,269 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{BPS_DENOMINATOR, MAX_TICK, MIN_TICK},
+    errors::ErrorCode,
+    math::{u256_div_ceil, U256},
+};
+
+/// floor(2^128 / sqrt(1.0001)^(2^i)) for i in 0..19, so any |tick| <= MAX_TICK is a product of these
+const INVERSE_SQRT_RATIOS_X128: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e2139,
+    0xfff2e50f5f656932ef12357cf3c7fdcb,
+    0xffe5caca7e10e4e61c3624eaa0941ccf,
+    0xffcb9843d60f6159c9db58835c926643,
+    0xff973b41fa98c081472e6896dfb254bf,
+    0xff2ea16466c96a3843ec78b326b52860,
+    0xfe5dee046a99a2a811c461f1969c3052,
+    0xfcbe86c7900a88aedcffc83b479aa3a3,
+    0xf987a7253ac413176f2b074cf7815e53,
+    0xf3392b0822b70005940c7a398e4b70f2,
+    0xe7159475a2c29b7443b29c7fa6e889d8,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e4,
+    0x70d869a156d2a1b890bb3df62baf32f6,
+    0x31be135f97d08fd981231505542fcfa5,
+    0x09aa508b5b7a84e1c677de54f3e99bc8,
+    0x005d6af8dedb81196699c329225ee604,
+    0x00002216e584f5fa1ea926041bedfe97,
+];
+
+/// Q64.64 square root price at `tick`, i.e. sqrt(1.0001^tick) * 2^64, rounded up.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), ErrorCode::InvalidTick);
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = U256::one() << 128;
+    for (bit, inverse_ratio) in INVERSE_SQRT_RATIOS_X128.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*inverse_ratio)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    let remainder = ratio & U256::from(u64::MAX);
+    let sqrt_price = (ratio >> 64) + if remainder.is_zero() { U256::zero() } else { U256::one() };
+    Ok(sqrt_price.as_u128())
+}
+
+/// Greatest tick whose square root price is at or below `sqrt_price_x64`.
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    require!(
+        sqrt_price_x64 >= sqrt_price_at_tick(MIN_TICK)? && sqrt_price_x64 <= sqrt_price_at_tick(MAX_TICK)?,
+        ErrorCode::InvalidTick
+    );
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price_x64 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Token A owed for `liquidity` between two square root prices: L * (hi - lo) / (hi * lo).
+pub fn amount_a_delta(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Result<u64> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    require!(lower > 0, ErrorCode::InvalidTick);
+    let numerator = (U256::from(liquidity) << 64)
+        .checked_mul(U256::from(upper - lower))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+    let amount = if round_up {
+        u256_div_ceil(u256_div_ceil(numerator, U256::from(upper)), U256::from(lower))
+    } else {
+        numerator / U256::from(upper) / U256::from(lower)
+    };
+    to_u64(amount)
+}
+
+/// Token B owed for `liquidity` between two square root prices: L * (hi - lo).
+pub fn amount_b_delta(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Result<u64> {
+    let (lower, upper) = sorted(sqrt_price_0, sqrt_price_1);
+    let product = U256::from(liquidity) * U256::from(upper - lower);
+    let amount = if round_up {
+        u256_div_ceil(product, U256::one() << 64)
+    } else {
+        product >> 64
+    };
+    to_u64(amount)
+}
+
+/// Square root price after adding `amount` of the input token; rounds against the trader.
+pub fn next_sqrt_price_from_input(sqrt_price_x64: u128, liquidity: u128, amount: u64, a_to_b: bool) -> Result<u128> {
+    require!(liquidity > 0, ErrorCode::InsufficientLiquidity);
+    if amount == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    let liquidity_x64 = U256::from(liquidity) << 64;
+    let next = if a_to_b {
+        // L * p / (L + amount * p), rounded up so the price moves no further than paid for
+        let numerator = liquidity_x64
+            .checked_mul(U256::from(sqrt_price_x64))
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        let denominator = liquidity_x64
+            .checked_add(U256::from(amount) * U256::from(sqrt_price_x64))
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?;
+        u256_div_ceil(numerator, denominator)
+    } else {
+        U256::from(sqrt_price_x64)
+            .checked_add((U256::from(amount) << 64) / U256::from(liquidity))
+            .ok_or(error!(ErrorCode::ArithmeticOverflow))?
+    };
+    require!(next.bits() <= 128, ErrorCode::ArithmeticOverflow);
+    Ok(next.as_u128())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// One exact-in swap step from the current price towards `sqrt_price_target_x64` within a
+/// single liquidity range. `amount_in + fee_amount` never exceeds `amount_remaining`.
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_bps: u16,
+    a_to_b: bool,
+) -> Result<SwapStep> {
+    require!((fee_bps as u64) < BPS_DENOMINATOR, ErrorCode::InvalidFee);
+    if liquidity == 0 {
+        return Ok(SwapStep {
+            sqrt_price_next_x64: sqrt_price_target_x64,
+            amount_in: 0,
+            amount_out: 0,
+            fee_amount: 0,
+        });
+    }
+
+    let fee_complement = BPS_DENOMINATOR - fee_bps as u64;
+    let amount_less_fee = ((amount_remaining as u128) * fee_complement as u128 / BPS_DENOMINATOR as u128) as u64;
+    let input_to_target = input_delta(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, a_to_b);
+
+    let (sqrt_price_next_x64, amount_in, fee_amount) = match input_to_target {
+        Ok(amount_in) if amount_less_fee >= amount_in => {
+            let fee_amount = ((amount_in as u128) * fee_bps as u128).div_ceil(fee_complement as u128) as u64;
+            (sqrt_price_target_x64, amount_in, fee_amount)
+        }
+        _ => {
+            let next = next_sqrt_price_from_input(sqrt_price_current_x64, liquidity, amount_less_fee, a_to_b)?;
+            let amount_in = input_delta(sqrt_price_current_x64, next, liquidity, a_to_b)?;
+            // Whatever is left after the input is the fee, so remainders never stay with the trader
+            (next, amount_in, amount_remaining - amount_in)
+        }
+    };
+
+    let amount_out = if a_to_b {
+        amount_b_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, false)?
+    } else {
+        amount_a_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, false)?
+    };
+    Ok(SwapStep {
+        sqrt_price_next_x64,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+fn input_delta(sqrt_price_current_x64: u128, sqrt_price_next_x64: u128, liquidity: u128, a_to_b: bool) -> Result<u64> {
+    if a_to_b {
+        amount_a_delta(sqrt_price_next_x64, sqrt_price_current_x64, liquidity, true)
+    } else {
+        amount_b_delta(sqrt_price_current_x64, sqrt_price_next_x64, liquidity, true)
+    }
+}
+
+fn sorted(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn to_u64(value: U256) -> Result<u64> {
+    require!(value.bits() <= 64, ErrorCode::ArithmeticOverflow);
+    Ok(value.as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_price_matches_reference_values() {
+        // ceil(sqrt(1.0001^tick) * 2^64), computed with 60-digit decimal arithmetic
+        let cases: [(i32, u128); 7] = [
+            (MIN_TICK, 4_295_048_017),
+            (-200_000, 837_899_702_510_259),
+            (-1_000, 17_547_129_613_991_598_782),
+            (0, 1 << 64),
+            (1_000, 19_392_480_388_906_836_278),
+            (200_000, 406_113_483_393_643_373_014_940),
+            (MAX_TICK, 79_226_673_515_401_279_992_447_579_062),
+        ];
+        for (tick, expected) in cases {
+            let actual = sqrt_price_at_tick(tick).unwrap();
+            assert!(actual.abs_diff(expected) <= 1, "tick={} {} vs {}", tick, actual, expected);
+        }
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_inverts_sqrt_price_at_tick() {
+        for tick in [MIN_TICK, -50_123, -7, 0, 3, 64_000, MAX_TICK - 1] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            let next = sqrt_price_at_tick(tick + 1).unwrap();
+            assert_eq!(tick_at_sqrt_price(next - 1).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn swap_step_stops_at_target_when_input_suffices() {
+        let current = sqrt_price_at_tick(0).unwrap();
+        let target = sqrt_price_at_tick(-100).unwrap();
+        let step = compute_swap_step(current, target, 1_000_000_000, u64::MAX / 4, 30, true).unwrap();
+        assert_eq!(step.sqrt_price_next_x64, target);
+        // ~0.5% move over L = 1e9 needs roughly 5e6 of token A
+        assert!((4_900_000..5_100_000).contains(&step.amount_in), "in={}", step.amount_in);
+        assert!(step.amount_out < step.amount_in);
+        assert!(step.fee_amount * 10_000 >= step.amount_in * 30);
+    }
+
+    #[test]
+    fn swap_step_consumes_whole_input_before_target() {
+        let current = sqrt_price_at_tick(0).unwrap();
+        let target = sqrt_price_at_tick(1_000).unwrap();
+        let liquidity = 5_000_000_000u128;
+        let step = compute_swap_step(current, target, liquidity, 1_000_000, 30, false).unwrap();
+        assert!(step.sqrt_price_next_x64 > current && step.sqrt_price_next_x64 < target);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000_000);
+        // Never pays out more than the constant-product equivalent for the same range
+        let ideal_out = amount_a_delta(current, step.sqrt_price_next_x64, liquidity, false).unwrap();
+        assert_eq!(step.amount_out, ideal_out);
+        assert!(step.amount_out < 1_000_000);
+    }
+
+    #[test]
+    fn liquidity_round_trip_favours_pool() {
+        let lower = sqrt_price_at_tick(-600).unwrap();
+        let upper = sqrt_price_at_tick(600).unwrap();
+        let liquidity = 123_456_789_012u128;
+        assert!(amount_a_delta(lower, upper, liquidity, true).unwrap() >= amount_a_delta(lower, upper, liquidity, false).unwrap());
+        assert!(amount_b_delta(lower, upper, liquidity, true).unwrap() >= amount_b_delta(lower, upper, liquidity, false).unwrap());
+    }
+}
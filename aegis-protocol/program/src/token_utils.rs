@@ -1,8 +1,21 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Burn, MintTo, Transfer};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as MintState;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TransferChecked};
 
 use crate::{errors::ErrorCode, validation};
 
+/// Token-2022 mint extensions a pool can hold without breaking its accounting.
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::InterestBearingConfig,
+];
+
 pub fn transfer_signed<'info>(
     token_program: AccountInfo<'info>,
     from: AccountInfo<'info>,
@@ -64,4 +77,94 @@ pub fn burn<'info>(
         ),
         amount,
     )
-}
\ No newline at end of file
+}
+
+pub fn transfer_checked<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    validation::assert_positive(amount)?;
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program,
+            TransferChecked {
+                from,
+                mint: mint.to_account_info(),
+                to,
+                authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Reloads `account` after a transfer and returns how much it gained since `balance_before`.
+/// Transfer-fee mints deliver less than the amount sent, so pools price on this instead.
+pub fn received_amount(account: &mut InterfaceAccount<TokenAccount>, balance_before: u64) -> Result<u64> {
+    account.reload()?;
+    account
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Amount that must be sent so that `net_amount` arrives after the mint's transfer fee.
+pub fn gross_up_for_transfer_fee(mint: &InterfaceAccount<Mint>, net_amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == token::ID {
+        return Ok(net_amount);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(net_amount);
+    };
+    let epoch = Clock::get()?.epoch;
+    fee_config
+        .calculate_inverse_epoch_fee(epoch, net_amount)
+        .and_then(|fee| net_amount.checked_add(fee))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Amount that arrives when `amount` is sent, after the mint's transfer fee.
+pub fn net_of_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == token::ID {
+        return Ok(amount);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let epoch = Clock::get()?.epoch;
+    fee_config
+        .calculate_epoch_fee(epoch, amount)
+        .and_then(|fee| amount.checked_sub(fee))
+        .ok_or(error!(ErrorCode::ArithmeticOverflow))
+}
+
+/// Rejects Token-2022 mints carrying extensions the pool cannot account for, such as
+/// transfer hooks, confidential transfers or permanent delegates.
+pub fn assert_supported_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == token::ID {
+        return Ok(());
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            ErrorCode::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    reentrancy,
+    state::{Observation, Pool, PoolObservations, OBSERVATION_CAPACITY},
+};
+
+#[derive(Accounts)]
+pub struct InitializeObservations<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = payer,
+        space = PoolObservations::SIZE,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump
+    )]
+    pub observations: Box<Account<'info, PoolObservations>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordObservation<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.mint_a.as_ref(), pool.mint_b.as_ref(), pool.fee_seed().as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump = observations.bump,
+        has_one = pool
+    )]
+    pub observations: Box<Account<'info, PoolObservations>>,
+}
+
+pub fn initialize_observations(ctx: Context<InitializeObservations>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let observations = &mut ctx.accounts.observations;
+    observations.pool = pool.key();
+    observations.index = 0;
+    observations.bump = ctx.bumps.observations;
+    observations.observations = [Observation::default(); OBSERVATION_CAPACITY];
+    observations.observations[0] = Observation {
+        timestamp: Clock::get()?.unix_timestamp,
+        price_a_cumulative: pool.price_a_cumulative,
+        price_b_cumulative: pool.price_b_cumulative,
+    };
+    Ok(())
+}
+
+/// Permissionless crank so observations keep being written while a pool is idle.
+pub fn record_observation(ctx: Context<RecordObservation>) -> Result<()> {
+    reentrancy::assert_unlocked(&*ctx.accounts.pool)?;
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    let (reserve_a, reserve_b) = (pool.reserve_a, pool.reserve_b);
+    update_cumulative_prices(pool, reserve_a, reserve_b, now)?;
+    write_observation(&mut ctx.accounts.observations, pool, now);
+    Ok(())
+}
+
+/// Q64.64 price of `numerator` in units of `denominator`; zero when the pool is empty.
+pub fn q64_price(numerator: u64, denominator: u64) -> u128 {
+    if numerator == 0 || denominator == 0 {
+        return 0;
+    }
+    ((numerator as u128) << 64) / denominator as u128
+}
+
+/// Accrues the pre-trade spot prices for the time elapsed since the last update.
+/// Must be called with the reserves as they were *before* the current instruction
+/// moves any tokens. Accumulators wrap on overflow by design; consumers only use differences.
+pub fn update_cumulative_prices(pool: &mut Pool, reserve_a: u64, reserve_b: u64, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(pool.last_price_update_ts);
+    if elapsed <= 0 {
+        return Ok(());
+    }
+    if reserve_a > 0 && reserve_b > 0 {
+        let elapsed = elapsed as u128;
+        pool.price_a_cumulative = pool
+            .price_a_cumulative
+            .wrapping_add(q64_price(reserve_b, reserve_a).wrapping_mul(elapsed));
+        pool.price_b_cumulative = pool
+            .price_b_cumulative
+            .wrapping_add(q64_price(reserve_a, reserve_b).wrapping_mul(elapsed));
+    }
+    pool.last_price_update_ts = now;
+    Ok(())
+}
+
+/// Appends a snapshot of the pool accumulators, at most one per second.
+pub fn write_observation(observations: &mut PoolObservations, pool: &Pool, now: i64) {
+    let index = observations.index as usize % OBSERVATION_CAPACITY;
+    if observations.observations[index].timestamp >= now {
+        return;
+    }
+    let next = (index + 1) % OBSERVATION_CAPACITY;
+    observations.observations[next] = Observation {
+        timestamp: now,
+        price_a_cumulative: pool.price_a_cumulative,
+        price_b_cumulative: pool.price_b_cumulative,
+    };
+    observations.index = next as u16;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_pool() -> Pool {
+        Pool::try_deserialize_unchecked(&mut [0u8; Pool::SIZE].as_slice()).unwrap()
+    }
+
+    fn empty_observations() -> PoolObservations {
+        PoolObservations::try_deserialize_unchecked(&mut [0u8; PoolObservations::SIZE].as_slice()).unwrap()
+    }
+
+    #[test]
+    fn accumulators_wrap_instead_of_overflowing() {
+        let mut pool = empty_pool();
+        pool.price_a_cumulative = u128::MAX - 1;
+        pool.price_b_cumulative = u128::MAX;
+        update_cumulative_prices(&mut pool, 1_000, 1_000, 2).unwrap();
+        // Two seconds at a price of 1.0 (1 << 64) wrap both accumulators around zero.
+        assert_eq!(pool.price_a_cumulative, (2u128 << 64) - 2);
+        assert_eq!(pool.price_b_cumulative, (2u128 << 64) - 1);
+        assert_eq!(pool.last_price_update_ts, 2);
+
+        // No time elapsed, nothing accrues.
+        update_cumulative_prices(&mut pool, 1_000, 4_000, 2).unwrap();
+        assert_eq!(pool.price_a_cumulative, (2u128 << 64) - 2);
+    }
+
+    #[test]
+    fn observations_are_written_at_most_once_per_second() {
+        let mut pool = empty_pool();
+        let mut observations = empty_observations();
+        pool.price_a_cumulative = 7;
+        write_observation(&mut observations, &pool, 10);
+        assert_eq!(observations.index, 1);
+
+        pool.price_a_cumulative = 8;
+        write_observation(&mut observations, &pool, 10);
+        assert_eq!(observations.index, 1);
+        assert_eq!(observations.observations[1].price_a_cumulative, 7);
+
+        write_observation(&mut observations, &pool, 11);
+        assert_eq!(observations.index, 2);
+        assert_eq!(observations.observations[2].price_a_cumulative, 8);
+    }
+
+    #[test]
+    fn observations_wrap_around_the_ring_buffer() {
+        let pool = empty_pool();
+        let mut observations = empty_observations();
+        for now in 1..=OBSERVATION_CAPACITY as i64 + 1 {
+            write_observation(&mut observations, &pool, now);
+        }
+        // The write after a full lap overwrites the oldest slot.
+        assert_eq!(observations.index, 1);
+        assert_eq!(observations.observations[0].timestamp, OBSERVATION_CAPACITY as i64);
+        assert_eq!(observations.observations[1].timestamp, OBSERVATION_CAPACITY as i64 + 1);
+    }
+}
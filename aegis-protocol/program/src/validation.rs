@@ -2,7 +2,7 @@ use anchor_lang::prelude::*;
 use crate::{
     constants::{DAY_IN_SECONDS, MAX_ALLOWED_PROGRAMS, MAX_DESCRIPTION_LEN},
     errors::ErrorCode,
-    state::Policy,
+    state::{ClPool, Policy, Pool},
 };
 
 pub fn assert_positive(amount: u64) -> Result<()> {
@@ -10,6 +10,29 @@ pub fn assert_positive(amount: u64) -> Result<()> {
     Ok(())
 }
 
+pub fn assert_deadline(deadline: i64, now: i64) -> Result<()> {
+    require!(now <= deadline, ErrorCode::DeadlineExceeded);
+    Ok(())
+}
+
+/// Pool reserves are lent out while a flash loan is open, so nothing may price off them.
+pub fn assert_no_flash_loan(pool: &Pool) -> Result<()> {
+    require!(!pool.flash_loan_active, ErrorCode::FlashLoanActive);
+    Ok(())
+}
+
+/// Set by the circuit breaker or the pool creator; no swap may price off the pool until it resumes.
+pub fn assert_not_emergency(pool: &Pool) -> Result<()> {
+    require!(!pool.emergency_mode, ErrorCode::PoolInEmergencyMode);
+    Ok(())
+}
+
+/// Concentrated pools have no circuit breaker; only their creator toggles emergency mode.
+pub fn assert_cl_pool_not_emergency(pool: &ClPool) -> Result<()> {
+    require!(!pool.emergency_mode, ErrorCode::PoolInEmergencyMode);
+    Ok(())
+}
+
 pub fn assert_program_allowed(policy: &Policy, program_id: &Pubkey) -> Result<()> {
     for i in 0..policy.allowed_programs_count as usize {
         if policy.allowed_programs[i] == *program_id {
@@ -264,12 +264,14 @@ export class Pool {
     // Discriminator from IDL for removeLiquidity
     const discriminator = Buffer.from([80, 85, 209, 72, 24, 206, 177, 108]);
     
-    // Serialize lp_amount (u64) in little-endian
-    const lpAmountBuffer = Buffer.alloc(8);
-    lpAmountBuffer.writeBigUInt64LE(BigInt(params.lpAmount.toString()));
+    // Serialize lp_amount, min_amount_a, min_amount_b (u64 each) in little-endian
+    const argsBuffer = Buffer.alloc(24);
+    argsBuffer.writeBigUInt64LE(BigInt(params.lpAmount.toString()), 0);
+    argsBuffer.writeBigUInt64LE(BigInt(params.minAmountA.toString()), 8);
+    argsBuffer.writeBigUInt64LE(BigInt(params.minAmountB.toString()), 16);
     
     // Combine discriminator + args
-    const data = Buffer.concat([discriminator, lpAmountBuffer]);
+    const data = Buffer.concat([discriminator, argsBuffer]);
 
     return new TransactionInstruction({
       keys: [
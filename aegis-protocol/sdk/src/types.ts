@@ -38,6 +38,8 @@ export interface SwapResult {
 
 export interface RemoveLiquidityParams {
   lpAmount: BN;
+  minAmountA: BN;
+  minAmountB: BN;
 }
 
 export interface RemoveLiquidityResult {